@@ -0,0 +1,515 @@
+//! Rewriting of exported function bodies
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::punctuated::Punctuated;
+
+use super::{params, types, Abi, Args, Func, Mode};
+
+/// Evaluates `block` to its value, an early `return` or `?` included, so the value can be post-processed
+///
+/// The body becomes an inner function taking the parameters, unlike a closure it can return `impl Trait` and
+/// destructure its parameters. The export binds each of them to a plain name to pass it on.
+pub(crate) fn eval(sig: &mut syn::Signature, block: &syn::Block) -> syn::Result<syn::Expr> {
+    let mut inputs = Punctuated::<syn::FnArg, syn::Token![,]>::new();
+    let mut args = Vec::new();
+    for (index, input) in sig.inputs.iter_mut().enumerate() {
+        let arg = match input {
+            syn::FnArg::Typed(arg) => arg,
+            syn::FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(receiver, "functions taking `self` cannot have their body rewritten"));
+            },
+        };
+        inputs.push(syn::FnArg::Typed(arg.clone()));
+        let ident = match &*arg.pat {
+            syn::Pat::Ident(pat) if pat.subpat.is_none() => pat.ident.clone(),
+            _ => quote::format_ident!("__reprfn_arg{}", index),
+        };
+        *arg.pat = syn::parse_quote! { #ident };
+        args.push(ident);
+    }
+
+    let syn::Signature { asyncness, unsafety, generics, output, .. } = &*sig;
+    let where_clause = &generics.where_clause;
+    // Lifetimes are inferred, and cannot be given explicitly when late bound
+    let turbofish = generics.params.iter().filter_map(|param| match param {
+        syn::GenericParam::Type(param) => Some(&param.ident),
+        syn::GenericParam::Const(param) => Some(&param.ident),
+        syn::GenericParam::Lifetime(_) => None,
+    }).collect::<Vec<_>>();
+    let turbofish = if turbofish.is_empty() { quote::quote! {} } else { quote::quote! { ::<#(#turbofish),*> } };
+    let call = quote::quote! { __reprfn_body #turbofish(#(#args),*) };
+    let call = if unsafety.is_some() { quote::quote! { unsafe { #call } } } else { call };
+    let call = if asyncness.is_some() { quote::quote! { #call.await } } else { call };
+    Ok(syn::parse_quote! {{
+        #[inline(always)]
+        #asyncness #unsafety fn __reprfn_body #generics(#inputs) #output #where_clause #block
+        #call
+    }})
+}
+
+/// Logs entering and leaving the function in debug builds
+pub(crate) fn trace(sig: &mut syn::Signature, block: &syn::Block) -> syn::Result<syn::Block> {
+    let value = eval(sig, block)?;
+    let name = sig.ident.to_string();
+    Ok(syn::parse_quote! {{
+        #[cfg(debug_assertions)]
+        ::std::eprintln!("enter {}", #name);
+        let value = #value;
+        #[cfg(debug_assertions)]
+        ::std::eprintln!("exit {}", #name);
+        value
+    }})
+}
+
+/// Checks the body is a single `naked_asm!` invocation, the only body a naked function may have
+fn check_asm_only(block: &syn::Block) -> syn::Result<()> {
+    let mac = match block.stmts.as_slice() {
+        [syn::Stmt::Macro(stmt)] => &stmt.mac,
+        [syn::Stmt::Expr(syn::Expr::Macro(expr), _)] => &expr.mac,
+        _ => return Err(syn::Error::new_spanned(block, "naked functions must have a body consisting of a single `naked_asm!` invocation")),
+    };
+    match mac.path.segments.last() {
+        Some(segment) if segment.ident == "naked_asm" => Ok(()),
+        // `asm!` expects a prologue to have set up the frame, naked functions have none
+        Some(segment) if segment.ident == "asm" => Err(syn::Error::new_spanned(&mac.path, "naked functions must use `naked_asm!`, not `asm!`")),
+        _ => Err(syn::Error::new_spanned(block, "naked functions must have a body consisting of a single `naked_asm!` invocation")),
+    }
+}
+
+/// `const extern fn` is fine on exports, but extern blocks cannot declare one and the runtime wrappers keep it out of const
+pub(crate) fn check_const(args: &Args, func: &Func) -> syn::Result<()> {
+    let Some(constness) = &func.sig.constness else {
+        return Ok(());
+    };
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new_spanned(constness, "`const` functions cannot be imported"));
+    }
+    let runtime = [
+        (args.debug_trace, "debug_trace"),
+        (args.string_return.is_some(), "string_return"),
+        (args.result_abi.is_some(), "result_abi"),
+        (args.errno.is_some(), "errno"),
+        (args.panic_hook, "panic_hook"),
+        (args.multiversion.is_some(), "multiversion"),
+        (args.forward, "forward"),
+        (args.opaque.is_some(), "opaque"),
+    ];
+    if let Some((_, option)) = runtime.iter().find(|(used, _)| *used) {
+        return Err(syn::Error::new_spanned(constness, format!("`{}` cannot be used on `const` functions", option)));
+    }
+    Ok(())
+}
+
+/// `mode = "forward"`, the body calls the import named `import_name` and the `link` goes on its declaration
+pub(crate) fn forward(args: &mut Args, func: &mut Func) -> syn::Result<()> {
+    if !args.forward {
+        if let Some(import_name) = &args.import_name {
+            return Err(syn::Error::new_spanned(import_name, "`import_name` can only be used with `mode = \"forward\"`"));
+        }
+        return Ok(());
+    }
+    let link = args.link.take();
+    let ident = &func.sig.ident;
+    let Some(import_name) = &args.import_name else {
+        return Err(syn::Error::new_spanned(ident, "`mode = \"forward\"` needs the `import_name` to forward to"));
+    };
+    if let Some(stmt) = func.block.stmts.first() {
+        return Err(syn::Error::new_spanned(stmt, "forwarded functions cannot have a body, it calls the import"));
+    }
+    let Abi::Explicit(abi_value) = &args.abi else {
+        return Err(syn::Error::new_spanned(ident, format!("Missing ABI for forwarded function '{}'", ident)));
+    };
+    if let Some(param) = func.sig.generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "forwarded functions cannot be generic"));
+    }
+    if let Some(variadic) = &func.sig.variadic {
+        return Err(syn::Error::new_spanned(variadic, "variadic arguments cannot be forwarded"));
+    }
+    params::reject(&func.sig.inputs, "parameter hints can only be used on exported functions")?;
+
+    let link_quote = match link {
+        Some(link_value) => link_value.quote(),
+        None => quote::quote! {},
+    };
+
+    let import_ident = quote::format_ident!("__reprfn_{}_import", ident);
+    let params::Forward { inputs: forward_inputs, args: forward_args } = params::forward(&func.sig.inputs)?;
+    let (feature_quote, output) = (&func.feature_quote, &func.sig.output);
+    func.aux.extend(quote::quote! {
+        #feature_quote
+        #link_quote
+        unsafe extern #abi_value {
+            #[link_name = #import_name]
+            #[allow(dead_code)]
+            fn #import_ident(#forward_inputs) #output;
+        }
+    });
+    *func.block = syn::parse_quote! {{
+        unsafe { #import_ident(#(#forward_args),*) }
+    }};
+    func.sig.inputs = forward_inputs;
+    Ok(())
+}
+
+/// The options rewriting what the export returns, or how, in the order they wrap each other
+pub(crate) fn transform(args: &Args, func: &mut Func) -> syn::Result<()> {
+    if args.debug_trace {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new_spanned(&func.sig.ident, "`debug_trace` can only be used on exported functions"));
+        }
+        *func.block = trace(&mut func.sig, &func.block)?;
+    }
+    if let Some(struct_ident) = &args.tuple_return {
+        tuple_return(struct_ident, func)?;
+    }
+
+    if let Some(status_enum) = &args.status_enum {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new_spanned(&status_enum.ident, "`status_enum` can only be used on exported functions"));
+        }
+        status_enum.apply_output(&mut func.sig.output)?;
+        func.aux.extend(status_enum.quote(&func.vis, &func.feature_quote));
+    }
+
+    if let Some(string_return) = &args.string_return {
+        self::string_return(args, string_return, func)?;
+    }
+    if let Some(opaque) = &args.opaque {
+        self::opaque(args, opaque, func)?;
+    }
+    if let Some(result_abi) = &args.result_abi {
+        self::result_abi(result_abi, func)?;
+    }
+    if let Some((setter, span)) = &args.errno {
+        errno(args, setter, *span, func)?;
+    }
+    if args.return_via_ptr {
+        return_via_ptr(func)?;
+    }
+    if let Some(features) = &args.multiversion {
+        multiversion(features, func)?;
+    }
+    Ok(())
+}
+
+/// Tuples have no C layout, they are returned through a generated `#[repr(C)]` struct instead
+fn tuple_return(struct_ident: &syn::Ident, func: &mut Func) -> syn::Result<()> {
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new_spanned(struct_ident, "`tuple_return` can only be used on exported functions"));
+    }
+    let elems = match &func.sig.output {
+        syn::ReturnType::Type(_, ty) => match &**ty {
+            syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => tuple.elems.iter().cloned().collect::<Vec<_>>(),
+            ty => return Err(syn::Error::new_spanned(ty, "`tuple_return` expects the function to return a tuple")),
+        },
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new_spanned(struct_ident, "`tuple_return` expects the function to return a tuple"));
+        },
+    };
+    for elem in &elems {
+        types::check_ffi_safe(elem)?;
+    }
+
+    let fields = (0..elems.len()).map(|index| quote::format_ident!("_{}", index)).collect::<Vec<_>>();
+    let indices = (0..elems.len()).map(syn::Index::from);
+    let value = eval(&mut func.sig, &func.block)?;
+    *func.block = syn::parse_quote! {{
+        let value = #value;
+        #struct_ident { #(#fields: value.#indices),* }
+    }};
+    func.sig.output = syn::parse_quote! { -> #struct_ident };
+    let (vis, feature_quote) = (&func.vis, &func.feature_quote);
+    func.aux.extend(quote::quote! {
+        #feature_quote
+        #[repr(C)]
+        #[allow(dead_code)]
+        #vis struct #struct_ident {
+            #(pub #fields: #elems),*
+        }
+    });
+    Ok(())
+}
+
+/// The string is handed to C as a leaked `CString`, which only the generated `_free` function may reclaim
+fn string_return(args: &Args, string_return: &syn::LitStr, func: &mut Func) -> syn::Result<()> {
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(string_return.span(), "`string_return` can only be used on exported functions"));
+    }
+    let is_string = matches!(&func.sig.output, syn::ReturnType::Type(_, ty) if matches!(&**ty, syn::Type::Path(ty) if ty.path.segments.last().is_some_and(|segment| segment.ident == "String")));
+    if !is_string {
+        return Err(syn::Error::new_spanned(&func.sig.output, "`string_return` expects the function to return a `String`"));
+    }
+
+    let value = eval(&mut func.sig, &func.block)?;
+    *func.block = syn::parse_quote! {{
+        let value = #value;
+        match ::std::ffi::CString::new(value) {
+            Ok(value) => value.into_raw(),
+            Err(_) => ::core::ptr::null_mut(),
+        }
+    }};
+    func.sig.output = syn::parse_quote! { -> *mut ::std::ffi::c_char };
+
+    let free_ident = quote::format_ident!("{}_free", func.sig.ident);
+    let free_name_quote = match &args.name {
+        Some(name_value) => {
+            let free_name = syn::LitStr::new(&format!("{}_free", name_value.value()), name_value.span());
+            quote::quote! { #[export_name = #free_name] }
+        },
+        None => quote::quote! { #[no_mangle] },
+    };
+    let (vis, feature_quote) = (&func.vis, &func.feature_quote);
+    func.aux.extend(quote::quote! {
+        /// Frees a string returned by the function, null is ignored
+        #feature_quote
+        #free_name_quote
+        #vis unsafe extern "C" fn #free_ident(p: *mut ::std::ffi::c_char) {
+            if !p.is_null() {
+                ::core::mem::drop(unsafe { ::std::ffi::CString::from_raw(p) });
+            }
+        }
+    });
+    Ok(())
+}
+
+/// C only ever sees a pointer to the boxed value, which it hands back to the destructor
+fn opaque(args: &Args, opaque: &syn::LitBool, func: &mut Func) -> syn::Result<()> {
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(opaque.span(), "`opaque` can only be used on exported functions"));
+    }
+    let boxed = match &func.sig.output {
+        syn::ReturnType::Type(_, ty) => types::boxed(ty).cloned(),
+        syn::ReturnType::Default => None,
+    };
+    let Some(handle_ty) = boxed else {
+        return Err(syn::Error::new_spanned(&func.sig.output, "`opaque` expects the function to return a `Box<T>`"));
+    };
+
+    let value = eval(&mut func.sig, &func.block)?;
+    *func.block = syn::parse_quote! {{
+        let value = #value;
+        ::std::boxed::Box::into_raw(value)
+    }};
+    func.sig.output = syn::parse_quote! { -> *mut #handle_ty };
+
+    let destroy_ident = quote::format_ident!("{}_destroy", func.sig.ident);
+    let destroy_name_quote = match &args.name {
+        Some(name_value) => {
+            let destroy_name = syn::LitStr::new(&format!("{}_destroy", name_value.value()), name_value.span());
+            quote::quote! { #[export_name = #destroy_name] }
+        },
+        None => quote::quote! { #[no_mangle] },
+    };
+    let (vis, feature_quote) = (&func.vis, &func.feature_quote);
+    func.aux.extend(quote::quote! {
+        /// Drops a handle returned by the function, null is ignored
+        #feature_quote
+        #destroy_name_quote
+        #vis unsafe extern "C" fn #destroy_ident(p: *mut #handle_ty) {
+            if !p.is_null() {
+                ::core::mem::drop(unsafe { ::std::boxed::Box::from_raw(p) });
+            }
+        }
+    });
+    Ok(())
+}
+
+/// `Ok` values go through an out-pointer and errors become the returned code, `0` being success
+fn result_abi(result_abi: &syn::LitBool, func: &mut Func) -> syn::Result<()> {
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(result_abi.span(), "`result_abi` can only be used on exported functions"));
+    }
+    let ok_ty = match &func.sig.output {
+        syn::ReturnType::Type(_, ty) => types::result_ok(ty),
+        syn::ReturnType::Default => None,
+    };
+    let Some(ok_ty) = ok_ty.cloned() else {
+        return Err(syn::Error::new_spanned(&func.sig.output, "`result_abi` expects the function to return a `Result<T, E>`"));
+    };
+
+    let value = eval(&mut func.sig, &func.block)?;
+    let ok_quote = if matches!(&ok_ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty()) {
+        quote::quote! { 0 }
+    } else {
+        let out = syn::Ident::new("out", func.sig.ident.span());
+        if params::has_param(&func.sig.inputs, &out) {
+            return Err(syn::Error::new_spanned(&func.sig.inputs, "`result_abi` adds an `out` parameter, which is already taken"));
+        }
+        func.sig.inputs.push(syn::parse_quote! { #out: *mut #ok_ty });
+        // Null is checked for, but not a dangling pointer, which only the caller can vouch for
+        func.sig.unsafety.get_or_insert_with(Default::default);
+        // A null `out` discards the value
+        quote::quote! {{
+            if !#out.is_null() {
+                unsafe { ::core::ptr::write(#out, value) }
+            }
+            0
+        }}
+    };
+    *func.block = syn::parse_quote! {{
+        match #value {
+            Ok(value) => #ok_quote,
+            Err(error) => {
+                let code = ::reprfn_core::ErrorCode::error_code(&error);
+                ::core::debug_assert!(code != 0, "error codes must be nonzero, 0 reports success");
+                code
+            },
+        }
+    }};
+    func.sig.output = syn::parse_quote! { -> i32 };
+    Ok(())
+}
+
+/// POSIX style, errors return a sentinel and leave their code in a thread-local
+fn errno(args: &Args, setter: &syn::Path, span: proc_macro2::Span, func: &mut Func) -> syn::Result<()> {
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(span, "`errno` can only be used on exported functions"));
+    }
+    if args.result_abi.is_some() {
+        return Err(syn::Error::new(span, "`errno` and `result_abi` both report errors, only one can be used"));
+    }
+    let ok_ty = match &func.sig.output {
+        syn::ReturnType::Type(_, ty) => types::result_ok(ty),
+        syn::ReturnType::Default => None,
+    };
+    let Some(ok_ty) = ok_ty.cloned() else {
+        return Err(syn::Error::new_spanned(&func.sig.output, "`errno` expects the function to return a `Result<T, E>`"));
+    };
+
+    let value = eval(&mut func.sig, &func.block)?;
+    *func.block = syn::parse_quote! {{
+        match #value {
+            Ok(value) => value,
+            Err(error) => {
+                #setter(::reprfn_core::ErrorCode::error_code(&error));
+                <#ok_ty as ::reprfn_core::ErrorSentinel>::error_sentinel()
+            },
+        }
+    }};
+    func.sig.output = syn::parse_quote! { -> #ok_ty };
+    Ok(())
+}
+
+/// Some C APIs take an explicit out-pointer rather than relying on the implicit `sret` return
+fn return_via_ptr(func: &mut Func) -> syn::Result<()> {
+    let ident = &func.sig.ident;
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new_spanned(ident, "`return_via_ptr` can only be used on exported functions"));
+    }
+    let syn::ReturnType::Type(_, ty) = &func.sig.output else {
+        return Err(syn::Error::new_spanned(ident, "`return_via_ptr` expects the function to return a value"));
+    };
+    let out = syn::Ident::new("out", ident.span());
+    if params::has_param(&func.sig.inputs, &out) {
+        return Err(syn::Error::new_spanned(&func.sig.inputs, "`return_via_ptr` adds an `out` parameter, which is already taken"));
+    }
+    let out_param: syn::FnArg = syn::parse_quote! { #out: *mut #ty };
+    let value = eval(&mut func.sig, &func.block)?;
+    *func.block = syn::parse_quote! {{
+        ::core::debug_assert!(!#out.is_null(), "out parameter `out` is null");
+        let value = #value;
+        unsafe { ::core::ptr::write(#out, value) }
+    }};
+    func.sig.inputs.push(out_param);
+    func.sig.output = syn::ReturnType::Default;
+    // The caller vouches for `out`, which a safe function could not rely on
+    func.sig.unsafety.get_or_insert_with(Default::default);
+    Ok(())
+}
+
+/// One implementation per target feature, the export picks the best one at runtime
+fn multiversion(features: &[syn::LitStr], func: &mut Func) -> syn::Result<()> {
+    let Func { sig, block, mode, feature_quote, aux, .. } = func;
+    let syn::Signature { ident, unsafety, generics, inputs, output, .. } = sig;
+    if let Mode::Import = mode {
+        return Err(syn::Error::new_spanned(&*ident, "`multiversion` can only be used on exported functions"));
+    }
+    if let Some(param) = generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "`multiversion` cannot be used on generic functions"));
+    }
+    if !features.iter().any(|feature| feature.value() == "baseline") {
+        return Err(syn::Error::new_spanned(&*ident, "`multiversion` needs a \"baseline\" entry to fall back to"));
+    }
+
+    let params::Forward { inputs: forward_inputs, args: forward_args } = params::forward(inputs)?;
+    let mut dispatch_quote = TokenStream2::new();
+    for feature in features.iter().filter(|feature| feature.value() != "baseline") {
+        let suffix = feature.value().replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+        let impl_ident = quote::format_ident!("__reprfn_{}_{}", ident, suffix);
+        aux.extend(quote::quote! {
+            #feature_quote
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            #[target_feature(enable = #feature)]
+            #[allow(dead_code)]
+            unsafe fn #impl_ident(#inputs) #output #block
+        });
+        dispatch_quote.extend(quote::quote! {
+            if ::std::is_x86_feature_detected!(#feature) {
+                return unsafe { #impl_ident(#(#forward_args),*) };
+            }
+        });
+    }
+
+    let baseline_ident = quote::format_ident!("__reprfn_{}_baseline", ident);
+    aux.extend(quote::quote! {
+        #feature_quote
+        #[allow(dead_code)]
+        #unsafety fn #baseline_ident(#inputs) #output #block
+    });
+    **block = syn::parse_quote! {{
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            #dispatch_quote
+        }
+        #[allow(unused_unsafe)]
+        unsafe { #baseline_ident(#(#forward_args),*) }
+    }};
+    *inputs = forward_inputs;
+    Ok(())
+}
+
+/// Anything besides the assembly of a naked function runs without a prologue, which is undefined behavior
+///
+/// Checked before any option adds to the body, so the errors point at the option rather than at generated code.
+pub(crate) fn check_naked(args: &Args, func: &Func) -> syn::Result<()> {
+    // The handler writes its own prologue and `iret`/`reti`/`mret` epilogue, the ABI only names the vector entry
+    if let Some(naked_interrupt) = &args.naked_interrupt {
+        if !args.abi.value().as_deref().is_some_and(super::traits::is_interrupt) {
+            let message = format!("`naked_interrupt` needs an interrupt ABI, one of '{:?}'", super::traits::INTERRUPT_ABIS);
+            return Err(syn::Error::new(naked_interrupt.span(), message));
+        }
+    }
+    if !args.naked && args.naked_interrupt.is_none() {
+        return Ok(());
+    }
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new_spanned(&func.sig.ident, "`naked` can only be used on exported functions"));
+    }
+    check_asm_only(&func.block)?;
+    params::reject(&func.sig.inputs, "parameter hints convert in the body, naked functions only have `naked_asm!`")?;
+    let rewriting = [
+        (args.debug_trace, "debug_trace"),
+        (args.tuple_return.is_some(), "tuple_return"),
+        (args.status_enum.is_some(), "status_enum"),
+        (args.string_return.is_some(), "string_return"),
+        (args.opaque.is_some(), "opaque"),
+        (args.result_abi.is_some(), "result_abi"),
+        (args.errno.is_some(), "errno"),
+        (args.return_via_ptr, "return_via_ptr"),
+        (args.multiversion.is_some(), "multiversion"),
+        (args.panic_hook, "panic_hook"),
+        (args.forward, "forward"),
+    ];
+    if let Some((_, option)) = rewriting.iter().find(|(used, _)| *used) {
+        let message = format!("`{}` rewrites the body, which naked functions cannot have besides `naked_asm!`", option);
+        return Err(syn::Error::new_spanned(&func.sig.ident, message));
+    }
+    Ok(())
+}
+
+pub(crate) fn naked(args: &Args) -> TokenStream2 {
+    if !args.naked && args.naked_interrupt.is_none() {
+        return quote::quote! {};
+    }
+    quote::quote! { #[unsafe(naked)] }
+}
@@ -0,0 +1,321 @@
+//! Symbol names, given with `name` or derived from `snake_case` Rust names in the C naming convention of a library
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::ext::IdentExt;
+
+use super::{defaults, find_attr, link, package_hash, traits, validate_symbol_version, Args, Func, Mode, NoMangle};
+
+#[derive(Clone)]
+pub(crate) enum Convention {
+    /// `create_window` is `createWindow`
+    Camel,
+    /// `create_window` is `CreateWindow`
+    Pascal,
+    /// `create_window` is `CREATE_WINDOW`
+    ScreamingSnake,
+    /// `sdl_create_window` is `Sdl_CreateWindow`, the first word naming the library
+    LibCamel,
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Splits `CreateWindow` or `createWindow` into lowercase words
+fn camel_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c.to_ascii_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+impl Convention {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "camelCase" => Some(Convention::Camel),
+            "PascalCase" => Some(Convention::Pascal),
+            "SCREAMING_SNAKE_CASE" => Some(Convention::ScreamingSnake),
+            "LibCamel" => Some(Convention::LibCamel),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, words: &[&str]) -> String {
+        match self {
+            Convention::Camel => {
+                let (first, rest) = words.split_first().expect("identifiers have at least one word");
+                first.to_string() + &rest.iter().map(|word| capitalize(word)).collect::<String>()
+            },
+            Convention::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+            Convention::ScreamingSnake => words.join("_").to_ascii_uppercase(),
+            Convention::LibCamel => {
+                let (lib, rest) = words.split_first().expect("identifiers have at least one word");
+                format!("{}_{}", capitalize(lib), rest.iter().map(|word| capitalize(word)).collect::<String>())
+            },
+        }
+    }
+
+    fn reverse(&self, symbol: &str) -> String {
+        match self {
+            Convention::Camel | Convention::Pascal => camel_words(symbol).join("_"),
+            Convention::ScreamingSnake => symbol.to_ascii_lowercase(),
+            Convention::LibCamel => match symbol.split_once('_') {
+                Some((lib, rest)) => std::iter::once(lib.to_ascii_lowercase()).chain(camel_words(rest)).collect::<Vec<_>>().join("_"),
+                None => symbol.to_ascii_lowercase(),
+            },
+        }
+    }
+
+    /// The symbol of `ident`, which must be `snake_case` and come back unchanged from the symbol
+    pub(crate) fn symbol(&self, ident: &syn::Ident) -> syn::Result<String> {
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        let words = name.split('_').collect::<Vec<_>>();
+        if words.iter().any(|word| word.is_empty() || word.chars().any(|c| c.is_ascii_uppercase())) {
+            return Err(syn::Error::new_spanned(ident, format!("'{}' is not a snake_case name", name)));
+        }
+        if matches!(self, Convention::LibCamel) && words.len() < 2 {
+            return Err(syn::Error::new_spanned(ident, format!("'{}' has no library prefix to split off", name)));
+        }
+
+        let symbol = self.apply(&words);
+        // Digits have no case, `sha_256` and `sha256` would both become `Sha256`
+        if self.reverse(&symbol) != name {
+            return Err(syn::Error::new_spanned(ident, format!("'{}' does not round-trip through its symbol '{}'", name, symbol)));
+        }
+        Ok(symbol)
+    }
+}
+
+/// The symbol of the function, its Rust name unless `name` or an option deriving it gives another
+pub(crate) fn symbol(args: &Args, ident: &syn::Ident) -> String {
+    args.name.as_ref().map(syn::LitStr::value).unwrap_or_else(|| ident.unraw().to_string())
+}
+
+/// Resolves `name` from `link_name`, `from_name_convention`, `module_prefix`, `prefix` and `hash_suffix`
+pub(crate) fn resolve(args: &mut Args, attrs: &[syn::Attribute], ident: &syn::Ident) -> syn::Result<()> {
+    // The symbol an import links against, under the name of the attribute it becomes
+    if let Some(link_name) = &args.link_name {
+        if args.name.is_some() || args.name_targets.is_some() {
+            return Err(syn::Error::new(link_name.span(), "`link_name` and `name` both name the symbol, only use one of them"));
+        }
+        args.name = Some(link_name.clone());
+    }
+
+    // The convention replaces the Rust name, a prefix still goes in front of it
+    let converted_name = match &args.from_name_convention {
+        Some((convention, convention_value)) => {
+            if args.name.is_some() || args.name_targets.is_some() {
+                return Err(syn::Error::new(convention_value.span(), "`from_name_convention` derives the name, `name` cannot be used"));
+            }
+            Some(syn::LitStr::new(&convention.symbol(ident)?, convention_value.span()))
+        },
+        None => None,
+    };
+    // A macro cannot see the module path, `module_prefix` spells it out and `net::tcp` becomes `net_tcp`
+    let module_value = args.module_prefix.as_ref().map(|module_prefix| module_prefix.value().replace("::", "_"));
+    if let Some(name_value) = args.name.clone().filter(|name_value| name_value.value().contains("{module}")) {
+        let Some(module_value) = &module_value else {
+            return Err(syn::Error::new(name_value.span(), "the `{module}` placeholder of `name` needs a `module_prefix`"));
+        };
+        args.name = Some(syn::LitStr::new(&name_value.value().replace("{module}", module_value), name_value.span()));
+    }
+    let converted_name = match (&args.module_prefix, &module_value) {
+        (Some(module_prefix), Some(module_value)) if args.name.is_none() && args.name_targets.is_none() => {
+            if args.abi.lit().is_none_or(|abi| traits::is_rust(&abi.value())) {
+                let message = "`module_prefix` needs an unmangled symbol, use a non-Rust ABI or `{module}` in `name`";
+                return Err(syn::Error::new(module_prefix.span(), message));
+            }
+            let base = converted_name.as_ref().map(syn::LitStr::value).unwrap_or_else(|| ident.unraw().to_string());
+            Some(syn::LitStr::new(&format!("{}_{}", module_value, base), module_prefix.span()))
+        },
+        _ => converted_name,
+    };
+    let prefix_value = match &args.prefix {
+        Some(prefix) if prefix.value() == "none" => None,
+        Some(prefix) => Some(prefix.value()),
+        None => defaults::get().prefix,
+    };
+    if let Some(prefix_value) = prefix_value {
+        let unmangled = args.internal_abi.is_some() || args.abi.lit().is_some_and(|abi| !traits::is_rust(&abi.value()));
+        if let (Some(prefix), false) = (&args.prefix, unmangled) {
+            return Err(syn::Error::new(prefix.span(), "`prefix` needs an unmangled symbol, use a non-Rust ABI"));
+        }
+        let named = args.name.is_some() || args.name_targets.is_some() || find_attr(attrs, "export_name").is_some() || find_attr(attrs, "link_name").is_some();
+        if unmangled && !named {
+            let base = converted_name.as_ref().map(syn::LitStr::value).unwrap_or_else(|| ident.unraw().to_string());
+            args.name = Some(syn::LitStr::new(&format!("{}{}", prefix_value, base), ident.span()));
+        }
+    }
+    if args.name.is_none() {
+        args.name = converted_name;
+    }
+    // `#[export_name]` is what keeps the symbol of a Rust ABI function from being mangled
+    if let (Some(_), None, None) = (&args.internal_abi, &args.name, &args.name_targets) {
+        args.name = Some(syn::LitStr::new(&ident.unraw().to_string(), ident.span()));
+    }
+
+    // Each version of a plugin gets symbols of its own, `CARGO_PKG_*` are those of the crate being compiled
+    if let Some(hash_suffix) = &args.hash_suffix {
+        if args.name_targets.is_some() || args.abi.lit().is_none_or(|abi| traits::is_rust(&abi.value())) {
+            return Err(syn::Error::new(hash_suffix.span(), "`hash_suffix` needs a single unmangled symbol, use a non-Rust ABI"));
+        }
+        let package = format!("{}@{}", std::env::var("CARGO_PKG_NAME").unwrap_or_default(), std::env::var("CARGO_PKG_VERSION").unwrap_or_default());
+        let base = args.name.as_ref().map(syn::LitStr::value).unwrap_or_else(|| ident.unraw().to_string());
+        args.name = Some(syn::LitStr::new(&format!("{}_{}", base, package_hash(&package)), hash_suffix.span()));
+    }
+
+    if let (Some(attr), true) = (find_attr(attrs, "export_name"), args.name.is_some() || args.name_targets.is_some()) {
+        return Err(syn::Error::new_spanned(attr, "Duplicate `#[export_name]`, remove it and only use the `name` attribute"));
+    }
+    Ok(())
+}
+
+/// The naming options only one mode knows about
+pub(crate) fn check_mode(args: &Args, func: &Func) -> syn::Result<()> {
+    if let (Some(link_name), Mode::Export) = (&args.link_name, &func.mode) {
+        return Err(syn::Error::new(link_name.span(), "`link_name` can only be used on imported functions, exports are named with `name`"));
+    }
+    if let (Some(name), Mode::Import) = (&args.name, &func.mode) {
+        validate_symbol_version(name)?;
+    }
+    if let (Some((_, convention_value)), Mode::Export) = (&args.from_name_convention, &func.mode) {
+        return Err(syn::Error::new(convention_value.span(), "`from_name_convention` can only be used on imported functions"));
+    }
+    Ok(())
+}
+
+/// The `#[link_name]` or `#[export_name]`, and the `#[no_mangle]` of an export
+pub(crate) fn quote(args: &Args, func: &Func) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let ident = &func.sig.ident;
+    let no_mangle = find_attr(&func.attrs, "no_mangle").is_none() && !args.abi.is_rust();
+
+    // `export_name` unmangles the symbol as much as `no_mangle` does, so it follows the same policy
+    let policy_cfg = match &args.no_mangle {
+        Some((NoMangle::Never, policy_value)) if matches!(func.mode, Mode::Export) && (args.name.is_some() || args.name_targets.is_some()) => {
+            return Err(syn::Error::new(policy_value.span(), "`no_mangle = \"never\"` keeps the mangled name, `name` cannot be used"));
+        },
+        Some((NoMangle::Release, _)) => Some(quote::quote! { not(debug_assertions) }),
+        Some((NoMangle::Debug, _)) => Some(quote::quote! { debug_assertions }),
+        _ => None,
+    };
+    let export_name_quote = |name_value: &syn::LitStr| match &policy_cfg {
+        Some(policy_cfg) => quote::quote! { cfg_attr(#policy_cfg, export_name = #name_value) },
+        None => quote::quote! { export_name = #name_value },
+    };
+
+    // Imports name the symbol they link against, exports the one they define
+    let name_quote = if let Some(name_value) = &args.name {
+        if let Mode::Import = func.mode {
+            quote::quote_spanned! { name_value.span()=>
+                #[link_name = #name_value]
+            }
+        } else {
+            let export_name_quote = export_name_quote(name_value);
+            quote::quote_spanned! { name_value.span()=>
+                #[#export_name_quote]
+            }
+        }
+    } else if let Some(name_targets) = &args.name_targets {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new_spanned(ident, "per-target names can only be used on exported functions"));
+        }
+        name_targets.cfg_attr_quote(export_name_quote)
+    } else {
+        quote::quote! {}
+    };
+
+    // Nothing can call an anonymous function by its Rust name, so its symbol must be fixed in every build
+    if let Some(anon) = &args.anon {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new(anon.span(), "`anon` can only be used on exported functions"));
+        }
+        let unmangled = (!name_quote.is_empty() && policy_cfg.is_none()) || find_attr(&func.attrs, "no_mangle").is_some() || find_attr(&func.attrs, "export_name").is_some() || match &args.no_mangle {
+            Some((policy, _)) => matches!(policy, NoMangle::Always),
+            None => no_mangle,
+        };
+        if !unmangled || args.check_only {
+            return Err(syn::Error::new(anon.span(), "`anon` needs an unmangled symbol, use a non-Rust ABI or set `name`"));
+        }
+    }
+
+    // Without a policy the ABI decides, mangled symbols give better backtraces in debug builds
+    let no_mangle_quote = match &args.no_mangle {
+        Some((_, policy_value)) if matches!(func.mode, Mode::Import) => {
+            return Err(syn::Error::new(policy_value.span(), "`no_mangle` can only be used on exported functions"));
+        },
+        _ if find_attr(&func.attrs, "no_mangle").is_some() => quote::quote! {},
+        Some((NoMangle::Always, _)) => quote::quote! { #[no_mangle] },
+        Some((NoMangle::Release, _)) => quote::quote! { #[cfg_attr(not(debug_assertions), no_mangle)] },
+        Some((NoMangle::Debug, _)) => quote::quote! { #[cfg_attr(debug_assertions, no_mangle)] },
+        Some((NoMangle::Never, _)) => quote::quote! {},
+        None if no_mangle => quote::quote! { #[no_mangle] },
+        None => quote::quote! {},
+    };
+
+    // `.symver` needs a symbol to alias distinct from the versioned name it defines, so the definition is renamed
+    let (name_quote, no_mangle_quote) = match (&args.default_version, &func.mode) {
+        (Some(version), Mode::Export) if !args.check_only => {
+            if args.name_targets.is_some() || !matches!(args.no_mangle, None | Some((NoMangle::Always, _))) {
+                return Err(syn::Error::new(version.span(), "`default_version` needs a single symbol in every build, not one per target or profile"));
+            }
+            if let Some(attr) = find_attr(&func.attrs, "no_mangle").or_else(|| find_attr(&func.attrs, "export_name")) {
+                return Err(syn::Error::new_spanned(attr, "`default_version` renames the symbol it versions, remove the attribute and use `name`"));
+            }
+            let symbol = self::symbol(args, ident);
+            let internal = syn::LitStr::new(&format!("{}_v", symbol), version.span());
+            let symbol = syn::LitStr::new(&symbol, version.span());
+            let elf_cfg = link::elf_cfg();
+            let name_quote = quote::quote! {
+                #[cfg_attr(#elf_cfg, export_name = #internal)]
+                #[cfg_attr(not(#elf_cfg), export_name = #symbol)]
+            };
+            (name_quote, quote::quote! {})
+        },
+        _ => (name_quote, no_mangle_quote),
+    };
+
+    // The ABI stays so rustc still lints the signature, only the symbol is not exported yet
+    if args.check_only {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new_spanned(ident, "`check_only` can only be used on exported functions"));
+        }
+        return Ok((quote::quote! {}, quote::quote! {}));
+    }
+    Ok((name_quote, no_mangle_quote))
+}
+
+/// The suffixed symbol is only known at expansion time, consumers loading the plugin read it from here
+pub(crate) fn symbol_const(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let Some(hash_suffix) = &args.hash_suffix else {
+        return Ok(());
+    };
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(hash_suffix.span(), "`hash_suffix` can only be used on exported functions"));
+    }
+    let Func { vis, sig, feature_quote, .. } = &*func;
+    let ident = &sig.ident;
+    let name = &args.name;
+    let symbol_ident = quote::format_ident!("{}_SYMBOL", ident.unraw().to_string().to_uppercase());
+    let doc = format!("The symbol [`{}`] is exported as", ident);
+    let symbol_quote = quote::quote! {
+        #[doc = #doc]
+        #feature_quote
+        #[allow(dead_code)]
+        #vis const #symbol_ident: &str = #name;
+    };
+    func.aux.extend(symbol_quote);
+    Ok(())
+}
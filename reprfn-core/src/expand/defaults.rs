@@ -0,0 +1,48 @@
+//! Crate-wide defaults set by `#[reprfn_defaults(...)]`, consulted by every `#[reprfn]` expanded after it
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Stored as plain strings, spans do not outlive the macro invocation that created them
+#[derive(Clone, Default)]
+pub(crate) struct Defaults {
+    pub(crate) abi: Option<String>,
+    pub(crate) prefix: Option<String>,
+    /// Whether exports become imports under `--cfg reprfn_import`
+    pub(crate) import_cfg: bool,
+}
+
+/// The defaults of each crate, a proc macro server like rust-analyzer's expands several crates in one process
+static DEFAULTS: Mutex<Option<HashMap<String, Defaults>>> = Mutex::new(None);
+
+/// Cargo sets `CARGO_CRATE_NAME` for the crate being compiled, and for each crate a proc macro server expands
+fn crate_key() -> String {
+    std::env::var("CARGO_CRATE_NAME").unwrap_or_default()
+}
+
+pub(crate) fn set(defaults: Defaults) {
+    let mut crates = DEFAULTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    crates.get_or_insert_with(HashMap::new).insert(crate_key(), defaults);
+}
+
+pub(crate) fn get() -> Defaults {
+    let crates = DEFAULTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    crates.as_ref().and_then(|crates| crates.get(&crate_key())).cloned().unwrap_or_default()
+}
+
+pub(crate) fn parse(meta: syn::meta::ParseNestedMeta, defaults: &mut Defaults) -> syn::Result<()> {
+    if meta.path.is_ident("abi") {
+        let value: syn::LitStr = meta.value()?.parse()?;
+        defaults.abi = Some(super::validate_abi(value)?.value());
+    } else if meta.path.is_ident("prefix") {
+        let value: syn::LitStr = meta.value()?.parse()?;
+        super::validate_symbol(&value, "prefixes")?;
+        defaults.prefix = Some(value.value());
+    } else if meta.path.is_ident("import_cfg") {
+        let value: syn::LitBool = meta.value()?.parse()?;
+        defaults.import_cfg = value.value;
+    } else {
+        return Err(meta.error("unsupported reprfn_defaults property"));
+    }
+    Ok(())
+}
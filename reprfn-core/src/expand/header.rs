@@ -0,0 +1,136 @@
+//! Docs of the generated functions, C prototypes of exports included with `emit_header`
+
+use syn::punctuated::Punctuated;
+
+use super::{convention, Args, Func};
+
+/// `doc`, `since` and `hidden`, added to the docs written on the function
+pub(crate) fn doc_attrs(args: &Args, attrs: &mut Vec<syn::Attribute>) {
+    for line in &args.doc {
+        attrs.push(syn::parse_quote_spanned! { line.span()=> #[doc = #line] });
+    }
+
+    // Appended after the existing docs, as a paragraph of its own
+    if let Some(since_value) = &args.since {
+        if attrs.iter().any(|attr| attr.path().is_ident("doc")) {
+            attrs.push(syn::parse_quote! { #[doc = ""] });
+        }
+        let note = format!("Available since {}", since_value.value());
+        attrs.push(syn::parse_quote_spanned! { since_value.span()=> #[doc = #note] });
+    }
+
+    // Docs written on the function stay, rustdoc just leaves it out of the crate docs
+    if args.hidden {
+        attrs.push(syn::parse_quote! { #[doc(hidden)] });
+    }
+}
+
+/// Documented from the final signature, so it matches what C links against
+pub(crate) fn emit(args: &Args, func: &mut Func) -> syn::Result<()> {
+    if !args.emit_header {
+        return Ok(());
+    }
+    let symbol = convention::symbol(args, &func.sig.ident);
+    let prototype = prototype(&symbol, &func.sig.inputs, func.sig.variadic.is_some(), &func.sig.output)?;
+    if func.attrs.iter().any(|attr| attr.path().is_ident("doc")) {
+        func.attrs.push(syn::parse_quote! { #[doc = ""] });
+    }
+    func.attrs.push(syn::parse_quote! { #[doc = "```c"] });
+    func.attrs.push(syn::parse_quote! { #[doc = #prototype] });
+    func.attrs.push(syn::parse_quote! { #[doc = "```"] });
+    Ok(())
+}
+
+/// The C spelling of `ty`, limited to types with an obvious counterpart
+fn c_type(ty: &syn::Type) -> syn::Result<String> {
+    match ty {
+        syn::Type::Paren(ty) => c_type(&ty.elem),
+        syn::Type::Group(ty) => c_type(&ty.elem),
+        syn::Type::Ptr(ptr) => {
+            let pointee = c_type(&ptr.elem)?;
+            Ok(if ptr.mutability.is_some() { format!("{}*", pointee) } else { format!("const {}*", pointee) })
+        },
+        syn::Type::Reference(reference) => {
+            let pointee = c_type(&reference.elem)?;
+            Ok(if reference.mutability.is_some() { format!("{}*", pointee) } else { format!("const {}*", pointee) })
+        },
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => Ok("void".to_string()),
+        syn::Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last().unwrap();
+            if !segment.arguments.is_none() {
+                return Err(syn::Error::new_spanned(ty, "generic types have no C prototype"));
+            }
+            let name = segment.ident.to_string();
+            let c_name = match name.as_str() {
+                "u8" => "uint8_t",
+                "u16" => "uint16_t",
+                "u32" => "uint32_t",
+                "u64" => "uint64_t",
+                "i8" => "int8_t",
+                "i16" => "int16_t",
+                "i32" => "int32_t",
+                "i64" => "int64_t",
+                "usize" => "size_t",
+                "isize" => "ptrdiff_t",
+                "f32" => "float",
+                "f64" => "double",
+                "bool" => "bool",
+                "c_char" => "char",
+                "c_schar" => "signed char",
+                "c_uchar" => "unsigned char",
+                "c_short" => "short",
+                "c_ushort" => "unsigned short",
+                "c_int" => "int",
+                "c_uint" => "unsigned int",
+                "c_long" => "long",
+                "c_ulong" => "unsigned long",
+                "c_longlong" => "long long",
+                "c_ulonglong" => "unsigned long long",
+                "c_float" => "float",
+                "c_double" => "double",
+                "c_void" => "void",
+                "u128" | "i128" | "char" | "str" | "String" | "Vec" | "Box" | "Option" => {
+                    return Err(syn::Error::new_spanned(ty, format!("`{}` has no C prototype", name)));
+                },
+                // Anything else is taken to be a `#[repr(C)]` type declared under the same name in C
+                _ => return Ok(name),
+            };
+            Ok(c_name.to_string())
+        },
+        ty => Err(syn::Error::new_spanned(ty, "this type has no C prototype")),
+    }
+}
+
+/// `int32_t add(int32_t a, int32_t b);`
+fn prototype(
+    name: &str,
+    inputs: &Punctuated<syn::FnArg, syn::Token![,]>,
+    variadic: bool,
+    output: &syn::ReturnType,
+) -> syn::Result<String> {
+    let mut params = Vec::new();
+    for input in inputs {
+        let syn::FnArg::Typed(arg) = input else {
+            return Err(syn::Error::new_spanned(input, "functions taking `self` have no C prototype"));
+        };
+        let ty = c_type(&arg.ty)?;
+        params.push(match &*arg.pat {
+            // Parameters destructured in the body are only bound to a generated name for passing them on
+            syn::Pat::Ident(pat) if !pat.ident.to_string().starts_with("__reprfn_") => format!("{} {}", ty, pat.ident),
+            _ => ty,
+        });
+    }
+    if variadic {
+        params.push("...".to_string());
+    }
+    if params.is_empty() {
+        params.push("void".to_string());
+    }
+
+    let ret = match output {
+        syn::ReturnType::Default => "void".to_string(),
+        syn::ReturnType::Type(_, ty) if matches!(**ty, syn::Type::Never(_)) => "_Noreturn void".to_string(),
+        syn::ReturnType::Type(_, ty) => c_type(ty)?,
+    };
+    Ok(format!("{} {}({});", ret, name, params.join(", ")))
+}
@@ -0,0 +1,67 @@
+//! Panic callbacks registered from C, the glue behind `panic_hook`
+
+use syn::ext::IdentExt;
+
+use super::{Func, Mode};
+
+/// The callback C registers, called with the panic message as a pointer and a length
+const CONTRACT: &str = "Option<extern \"C\" fn(*const u8, usize)>";
+
+fn is_contract(ty: &syn::Type) -> bool {
+    let contract: syn::Type = syn::parse_str(CONTRACT).unwrap();
+    quote::quote!(#ty).to_string() == quote::quote!(#contract).to_string()
+}
+
+/// The setter stores the callback, `<ident>_invoke` calls it from the panic handler of the crate
+pub(crate) fn expand(func: &mut Func) -> syn::Result<()> {
+    let Func { vis, sig, block, mode, feature_quote, aux, .. } = func;
+    let syn::Signature { ident, generics, inputs, .. } = sig;
+    if let Mode::Import = mode {
+        return Err(syn::Error::new_spanned(&*ident, "`panic_hook` can only be used on exported functions"));
+    }
+    if let Some(stmt) = block.stmts.first() {
+        return Err(syn::Error::new_spanned(stmt, "`panic_hook` functions cannot have a body, it stores the callback"));
+    }
+    if let Some(param) = generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "`panic_hook` functions cannot be generic"));
+    }
+
+    let message = format!("`panic_hook` functions take a single `{}` parameter", CONTRACT);
+    let hook = match inputs.first() {
+        Some(syn::FnArg::Typed(arg)) if inputs.len() == 1 && is_contract(&arg.ty) => match &*arg.pat {
+            syn::Pat::Ident(pat) => &pat.ident,
+            pat => return Err(syn::Error::new_spanned(pat, "the `panic_hook` parameter must be bound to a plain identifier")),
+        },
+        _ => return Err(syn::Error::new_spanned(&*inputs, message)),
+    };
+
+    let static_ident = quote::format_ident!("__REPRFN_{}_PANIC_HOOK", ident.unraw().to_string().to_uppercase());
+    let invoke_ident = quote::format_ident!("{}_invoke", ident);
+    **block = syn::parse_quote! {{
+        let hook = match #hook {
+            Some(hook) => hook as *mut (),
+            None => ::core::ptr::null_mut(),
+        };
+        #static_ident.store(hook, ::core::sync::atomic::Ordering::Release);
+    }};
+    aux.extend(quote::quote! {
+        #feature_quote
+        #[allow(dead_code)]
+        static #static_ident: ::core::sync::atomic::AtomicPtr<()> = ::core::sync::atomic::AtomicPtr::new(::core::ptr::null_mut());
+
+        /// Passes `message` to the callback registered from C, returns whether one was registered
+        #feature_quote
+        #[allow(dead_code)]
+        #vis fn #invoke_ident(message: &str) -> bool {
+            let hook = #static_ident.load(::core::sync::atomic::Ordering::Acquire);
+            if hook.is_null() {
+                return false;
+            }
+            // Only the setter stores this pointer, always from a function of the contract type
+            let hook = unsafe { ::core::mem::transmute::<*mut (), extern "C" fn(*const u8, usize)>(hook) };
+            hook(message.as_ptr(), message.len());
+            true
+        }
+    });
+    Ok(())
+}
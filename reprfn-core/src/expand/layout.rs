@@ -0,0 +1,88 @@
+//! Expected sizes of the types crossing the ABI boundary, `{ ctx = 8, len = 4, return = 4 }`
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::punctuated::Punctuated;
+
+use super::{Args, Func, Mode};
+
+#[derive(Clone)]
+pub(crate) struct Layout {
+    sizes: Vec<(syn::Ident, syn::LitInt)>,
+}
+
+impl Layout {
+    pub(crate) fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::braced!(content in input);
+
+        let mut layout = Layout { sizes: Vec::new() };
+        while !content.is_empty() {
+            // `return` is a keyword, the other keys name parameters
+            let key = if content.peek(syn::Token![return]) {
+                let token: syn::Token![return] = content.parse()?;
+                syn::Ident::new("return", token.span)
+            } else {
+                content.parse()?
+            };
+            content.parse::<syn::Token![=]>()?;
+            let size: syn::LitInt = content.parse()?;
+            size.base10_parse::<usize>()?;
+
+            if layout.sizes.iter().any(|(existing, _)| *existing == key) {
+                return Err(syn::Error::new(key.span(), format!("duplicate size for '{}'", key)));
+            }
+            layout.sizes.push((key, size));
+
+            if !content.is_empty() {
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(layout)
+    }
+
+    /// One array length check per size, `[(); 8] = [(); size_of::<T>()]` fails to build on a mismatch
+    fn quote(
+        &self,
+        inputs: &Punctuated<syn::FnArg, syn::Token![,]>,
+        output: &syn::ReturnType,
+        feature_quote: &TokenStream2,
+    ) -> syn::Result<TokenStream2> {
+        let mut expanded = TokenStream2::new();
+        for (key, size) in &self.sizes {
+            let ty = if key == "return" {
+                match output {
+                    syn::ReturnType::Type(_, ty) => &**ty,
+                    syn::ReturnType::Default => return Err(syn::Error::new(key.span(), "the function returns nothing")),
+                }
+            } else {
+                let arg = inputs.iter().find_map(|input| match input {
+                    syn::FnArg::Typed(arg) if matches!(&*arg.pat, syn::Pat::Ident(pat) if pat.ident == *key) => Some(arg),
+                    _ => None,
+                });
+                match arg {
+                    Some(arg) => &*arg.ty,
+                    None => return Err(syn::Error::new(key.span(), format!("no parameter '{}' in the exported signature", key))),
+                }
+            };
+            let size = syn::LitInt::new(size.base10_digits(), size.span());
+            expanded.extend(quote::quote_spanned! { size.span()=>
+                #feature_quote
+                const _: [(); #size] = [(); ::core::mem::size_of::<#ty>()];
+            });
+        }
+        Ok(expanded)
+    }
+}
+
+/// Checked against the exported signature, after the hints and return transforms changed it
+pub(crate) fn expand(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let Some(assert_abi_layout) = &args.assert_abi_layout else {
+        return Ok(());
+    };
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new_spanned(&func.sig.ident, "`assert_abi_layout` can only be used on exported functions"));
+    }
+    let layout_quote = assert_abi_layout.quote(&func.sig.inputs, &func.sig.output, &func.feature_quote)?;
+    func.aux.extend(layout_quote);
+    Ok(())
+}
@@ -0,0 +1,448 @@
+//! Linking of imports and exports, libraries of imports are written `"foo"` or `{ name = "foo", kind = "static", modifiers = "+whole-archive" }`
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::ext::IdentExt;
+
+use super::{convention, find_attr, params, Args, FeatureTarget, Func, ImportStyle, Mode, Quotes};
+
+const KINDS: [&str; 4] = ["dylib", "static", "framework", "raw-dylib"];
+const IMPORT_NAME_TYPES: [&str; 3] = ["decorated", "noprefix", "undecorated"];
+
+#[derive(Clone)]
+pub(crate) struct Link {
+    name: syn::LitStr,
+    kind: Option<syn::LitStr>,
+    modifiers: Option<syn::LitStr>,
+    import_name_type: Option<syn::LitStr>,
+}
+
+impl Link {
+    /// `None` for `link = "none"`
+    pub(crate) fn parse(input: syn::parse::ParseStream) -> syn::Result<Option<Self>> {
+        if !input.peek(syn::token::Brace) {
+            let name: syn::LitStr = input.parse()?;
+            if name.value() == "none" {
+                return Ok(None);
+            }
+            return Ok(Some(Link { name, kind: None, modifiers: None, import_name_type: None }));
+        }
+
+        let content;
+        let braces = syn::braced!(content in input);
+        let (mut name, mut kind, mut modifiers, mut import_name_type) = (None, None, None, None);
+        while !content.is_empty() {
+            let key: syn::Ident = content.parse()?;
+            content.parse::<syn::Token![=]>()?;
+            let value: syn::LitStr = content.parse()?;
+
+            let slot = if key == "name" {
+                &mut name
+            } else if key == "kind" {
+                if !KINDS.contains(&value.value().as_str()) {
+                    return Err(syn::Error::new(value.span(), format!("invalid link kind '{}', expecting one of {:?}", value.value(), KINDS)));
+                }
+                &mut kind
+            } else if key == "modifiers" {
+                let valid = value.value().split(',').all(|modifier| modifier.len() > 1 && modifier.starts_with(['+', '-']));
+                if !valid {
+                    return Err(syn::Error::new(value.span(), format!("invalid link modifiers '{}', expecting a list like '+whole-archive,-bundle'", value.value())));
+                }
+                &mut modifiers
+            } else if key == "import_name_type" {
+                if !IMPORT_NAME_TYPES.contains(&value.value().as_str()) {
+                    return Err(syn::Error::new(value.span(), format!("invalid import_name_type '{}', expecting one of {:?}", value.value(), IMPORT_NAME_TYPES)));
+                }
+                &mut import_name_type
+            } else {
+                return Err(syn::Error::new(key.span(), format!("unknown link key '{}', expecting one of 'name', 'kind', 'modifiers' or 'import_name_type'", key)));
+            };
+            if slot.is_some() {
+                return Err(syn::Error::new(key.span(), format!("duplicate link key '{}'", key)));
+            }
+            *slot = Some(value);
+
+            if !content.is_empty() {
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        let Some(name) = name else {
+            return Err(syn::Error::new(braces.span.join(), "`link` needs the `name` of the library"));
+        };
+        let link = Link { name, kind, modifiers, import_name_type };
+        if let Some(import_name_type) = &link.import_name_type {
+            if !link.is_raw_dylib() {
+                return Err(syn::Error::new(import_name_type.span(), "`import_name_type` needs `kind = \"raw-dylib\"`"));
+            }
+        }
+        Ok(Some(link))
+    }
+
+    /// `raw-dylib` imports are resolved without an import library, optionally by ordinal
+    pub(crate) fn is_raw_dylib(&self) -> bool {
+        self.kind.as_ref().is_some_and(|kind| kind.value() == "raw-dylib")
+    }
+
+    pub(crate) fn span(&self) -> proc_macro2::Span {
+        self.name.span()
+    }
+
+    /// The `#[link(...)]` attribute
+    pub(crate) fn quote(&self) -> TokenStream2 {
+        let name = &self.name;
+        let kind = self.kind.iter();
+        let modifiers = self.modifiers.iter();
+        let import_name_type = self.import_name_type.iter();
+        quote::quote! {
+            #[link(name = #name #(, kind = #kind)* #(, modifiers = #modifiers)* #(, import_name_type = #import_name_type)*)]
+        }
+    }
+}
+
+/// The `#[link]` of an import, and the reference forcing the linker to resolve it with `require_link`
+pub(crate) fn import_quotes(args: &Args, func: &mut Func, abi_quote: &TokenStream2) -> syn::Result<(TokenStream2, TokenStream2)> {
+    // Windows resolves `raw-dylib` imports by ordinal instead of by name when one is given
+    if let Some(ordinal) = &args.ordinal {
+        if let Mode::Export = func.mode {
+            return Err(syn::Error::new(ordinal.span(), "`ordinal` can only be used on imported functions"));
+        }
+        if !args.link.as_ref().is_some_and(Link::is_raw_dylib) {
+            return Err(syn::Error::new(ordinal.span(), "`ordinal` needs `link = { name = \"...\", kind = \"raw-dylib\" }`"));
+        }
+        func.attrs.push(syn::parse_quote! { #[link_ordinal(#ordinal)] });
+    }
+
+    let link_quote = match (&func.mode, &args.link) {
+        (Mode::Import, Some(link_value)) => link_value.quote(),
+        (Mode::Export, Some(link_value)) => {
+            return Err(syn::Error::new(link_value.span(), "`link` can only be used on imported functions"));
+        },
+        (_, None) => quote::quote! {},
+    };
+
+    // Taking the address of the import forces the linker to resolve the symbol
+    let syn::Signature { ident, generics, inputs, variadic, output, .. } = &func.sig;
+    let require_link_quote = match (&func.mode, args.require_link) {
+        (Mode::Import, true) => {
+            if let Some(param) = generics.params.first() {
+                return Err(syn::Error::new_spanned(param, "`require_link` cannot be used on generic functions"));
+            }
+            let arg_types = inputs.iter().map(|arg| match arg {
+                syn::FnArg::Typed(arg) => Ok(&arg.ty),
+                syn::FnArg::Receiver(arg) => Err(syn::Error::new_spanned(arg, "imported functions cannot take `self`")),
+            }).collect::<syn::Result<Vec<_>>>()?;
+            let variadic_dots = variadic.as_ref().map(|variadic| &variadic.dots);
+            // Wrappers of the item style and swappable imports have the Rust ABI, the declaration they call is the
+            // one to link
+            let linked_ident = if args.is_wrapped() {
+                quote::format_ident!("__reprfn_{}_import", ident)
+            } else {
+                ident.clone()
+            };
+            let feature_quote = &func.feature_quote;
+            quote::quote! {
+                #feature_quote
+                const _: () = {
+                    #[used]
+                    static REQUIRE_LINK: unsafe #abi_quote fn(#(#arg_types,)* #variadic_dots) #output = #linked_ident;
+                };
+            }
+        },
+        (Mode::Export, true) => {
+            return Err(syn::Error::new_spanned(ident, "`require_link` can only be used on imported functions"));
+        },
+        (_, false) => quote::quote! {},
+    };
+    Ok((link_quote, require_link_quote))
+}
+
+/// Targets producing ELF objects, the only ones with symbol versions
+pub(crate) fn elf_cfg() -> TokenStream2 {
+    quote::quote! { not(any(windows, target_family = "wasm", target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos")) }
+}
+
+/// Exports the loader, the linker or the assembler treat specially: constructors, symbol versions, foldable
+/// duplicates and underscore aliases
+pub(crate) fn loader_aux(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let rust_abi = args.abi.is_rust();
+    let ident = func.sig.ident.clone();
+
+    // The loader calls every function pointer found in the platform init and fini sections
+    for (enabled, kind, elf, mach_o, coff) in [
+        (args.ctor, "ctor", ".init_array", "__DATA,__mod_init_func", ".CRT$XCU"),
+        (args.dtor, "dtor", ".fini_array", "__DATA,__mod_term_func", ".CRT$XTU"),
+    ] {
+        if !enabled {
+            continue;
+        }
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new_spanned(&ident, format!("`{}` can only be used on exported functions", kind)));
+        }
+        if args.abi.value().as_deref() != Some("C") {
+            return Err(syn::Error::new_spanned(&ident, format!("`{}` functions are called by the loader and must use the \"C\" ABI", kind)));
+        }
+        if !func.sig.inputs.is_empty() || !matches!(func.sig.output, syn::ReturnType::Default) {
+            return Err(syn::Error::new_spanned(&ident, format!("`{}` functions must take no parameters and return `()`", kind)));
+        }
+        let (unsafety, feature_quote) = (&func.sig.unsafety, &func.feature_quote);
+        func.aux.extend(quote::quote! {
+            #feature_quote
+            const _: () = {
+                #[used]
+                #[cfg_attr(any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos"), link_section = #mach_o)]
+                #[cfg_attr(windows, link_section = #coff)]
+                #[cfg_attr(not(any(windows, target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos")), link_section = #elf)]
+                static HOOK: #unsafety extern "C" fn() = #ident;
+            };
+        });
+    }
+
+    // Symbol versions only exist in ELF, `@@` makes this the version new links bind to
+    if let Some(version) = &args.default_version {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new(version.span(), "`default_version` can only be used on exported functions"));
+        }
+        if rust_abi {
+            return Err(syn::Error::new(version.span(), "`default_version` needs an unmangled symbol, use a non-Rust ABI"));
+        }
+        // `{0}` is the internal `<symbol>_v` the function is defined as on ELF targets, see `convention::quote`
+        let symbol = convention::symbol(args, &ident).replace('{', "{{").replace('}', "}}");
+        let directive = syn::LitStr::new(&format!(".symver {{0}}, {}@@{}", symbol, version.value()), version.span());
+        let (feature_quote, elf_cfg) = (&func.feature_quote, elf_cfg());
+        func.aux.extend(quote::quote! {
+            #feature_quote
+            #[cfg(#elf_cfg)]
+            ::core::arch::global_asm!(#directive, sym #ident);
+        });
+    }
+
+    // Rust has no comdats, a `linkonce_odr` definition in a section of its own is what the linker folds duplicates of
+    if let Some(dedup) = &args.dedup {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new(dedup.span(), "`dedup` can only be used on exported functions"));
+        }
+        if rust_abi {
+            return Err(syn::Error::new(dedup.span(), "`dedup` needs an unmangled symbol, use a non-Rust ABI"));
+        }
+        if args.name_targets.is_some() {
+            return Err(syn::Error::new(dedup.span(), "`dedup` needs a single symbol name, not one per target"));
+        }
+        if let Some(attr) = find_attr(&func.attrs, "link_section") {
+            return Err(syn::Error::new_spanned(attr, "`dedup` places the function in a section of its own, remove `#[link_section]`"));
+        }
+        let symbol = convention::symbol(args, &ident);
+        let (elf, coff) = (format!(".text.{}", symbol), format!(".text$rf_{}", symbol));
+        func.attrs.push(syn::parse_quote! { #[linkage = "linkonce_odr"] });
+        func.attrs.push(syn::parse_quote! { #[cfg_attr(windows, link_section = #coff)] });
+        func.attrs.push(syn::parse_quote! { #[cfg_attr(not(windows), link_section = #elf)] });
+        let feature_quote = &func.feature_quote;
+        func.aux.extend(quote::quote_spanned! { dedup.span()=>
+            #feature_quote
+            #[cfg(any(target_vendor = "apple", target_family = "wasm"))]
+            ::core::compile_error!("`dedup` needs an ELF or COFF target, Mach-O and wasm objects have no comdat groups");
+        });
+    }
+
+    // `{0}` is the symbol as the object file spells it, so `_{0}` is `_name` to C whether the target prefixes
+    // C symbols with an underscore, like Mach-O, or not
+    if let Some(underscore_alias) = &args.underscore_alias {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new(underscore_alias.span(), "`underscore_alias` can only be used on exported functions"));
+        }
+        if rust_abi {
+            return Err(syn::Error::new(underscore_alias.span(), "`underscore_alias` needs an unmangled symbol, use a non-Rust ABI"));
+        }
+        let symbol = convention::symbol(args, &ident);
+        let valid = symbol.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && symbol.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(syn::Error::new(underscore_alias.span(), format!("`underscore_alias` needs a C identifier as the symbol name, found '{}'", symbol)));
+        }
+        let feature_quote = &func.feature_quote;
+        func.aux.extend(quote::quote! {
+            #feature_quote
+            #[cfg(not(target_family = "wasm"))]
+            ::core::arch::global_asm!(".globl _{0}", ".set _{0}, {0}", sym #ident);
+        });
+    }
+    Ok(())
+}
+
+/// Takes the exported signature, Rust callers skip nothing but the symbol
+pub(crate) fn rust_wrapper(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let Some(rust_wrapper) = &args.rust_wrapper else {
+        return Ok(());
+    };
+    let Func { vis, sig, mode, feature_quote, aux, .. } = func;
+    let syn::Signature { constness, unsafety, ident, generics, inputs, variadic, output, .. } = &*sig;
+    let syn::Generics { lt_token, params, gt_token, where_clause } = generics;
+    if let Mode::Import = mode {
+        return Err(syn::Error::new(rust_wrapper.span(), "`rust_wrapper` can only be used on exported functions"));
+    }
+    if args.abi.is_rust() {
+        return Err(syn::Error::new(rust_wrapper.span(), "`rust_wrapper` needs a non-Rust ABI, the function already is a Rust one"));
+    }
+    if let Some(variadic) = variadic {
+        return Err(syn::Error::new_spanned(variadic, "variadic functions cannot be wrapped"));
+    }
+    if let Some(syn::FnArg::Receiver(receiver)) = inputs.first() {
+        return Err(syn::Error::new_spanned(receiver, "functions taking `self` cannot be wrapped"));
+    }
+    let params::Forward { inputs: forward_inputs, args: forward_args } = params::forward(inputs)?;
+    let wrapper_ident = quote::format_ident!("{}_rust", ident);
+    let call_quote = if unsafety.is_some() {
+        quote::quote! { unsafe { #ident(#(#forward_args),*) } }
+    } else {
+        quote::quote! { #ident(#(#forward_args),*) }
+    };
+    let doc = format!("Calls [`{}`] with the Rust ABI", ident);
+    aux.extend(quote::quote! {
+        #[doc = #doc]
+        #feature_quote
+        #[inline]
+        #[allow(dead_code)]
+        #vis #constness #unsafety fn #wrapper_ident #lt_token #params #gt_token(#forward_inputs) #output #where_clause {
+            #call_quote
+        }
+    });
+    Ok(())
+}
+
+/// Extern blocks are the only way to declare an import, the item style hides one behind a regular function
+pub(crate) fn item_style(args: &Args, func: &Func, quotes: &Quotes) -> syn::Result<Option<TokenStream2>> {
+    let Some((ImportStyle::Item, style_value)) = &args.import_style else {
+        return Ok(None);
+    };
+    let Func { attrs, vis, sig, feature_quote, wrapper_feature_quote, .. } = func;
+    let syn::Signature { fn_token, ident, generics, inputs, variadic, output, .. } = sig;
+    if let Mode::Export = func.mode {
+        return Err(syn::Error::new(style_value.span(), "`import_style` can only be used on imported functions"));
+    }
+    if let Some(param) = generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "`import_style = \"item\"` cannot be used on generic functions"));
+    }
+    if let Some(variadic) = variadic {
+        return Err(syn::Error::new_spanned(variadic, "variadic arguments cannot be wrapped in a function item"));
+    }
+
+    let link_name = args.name.clone().unwrap_or_else(|| syn::LitStr::new(&ident.unraw().to_string(), ident.span()));
+    let import_ident = quote::format_ident!("__reprfn_{}_import", ident);
+    let params::Forward { inputs: forward_inputs, args: forward_args } = params::forward(inputs)?;
+    let wrapper_unsafety = if args.safe { quote::quote! {} } else { quote::quote! { unsafe } };
+    let Quotes { abi: abi_quote, then: then_quote, cold: cold_quote, ffi: ffi_quote, link: link_quote, require_link: require_link_quote, unsafe_block: unsafe_block_quote, safe: safe_quote } = quotes;
+    Ok(Some(quote::quote! {
+        #link_quote
+        #unsafe_block_quote #abi_quote {
+            #feature_quote
+            #[link_name = #link_name]
+            #[allow(dead_code)]
+            #ffi_quote
+            #safe_quote #fn_token #import_ident(#forward_inputs) #output;
+        }
+        #then_quote
+        #(#attrs)*
+        #wrapper_feature_quote
+        #cold_quote
+        #[inline]
+        #vis #wrapper_unsafety #fn_token #ident(#forward_inputs) #output {
+            #[allow(unused_unsafe)]
+            unsafe { #import_ident(#(#forward_args),*) }
+        }
+        #require_link_quote
+    }))
+}
+
+/// Calls go through a pointer that starts out null, standing for the linked symbol, until a replacement is set
+pub(crate) fn swappable(args: &Args, func: &Func, quotes: &Quotes) -> syn::Result<Option<TokenStream2>> {
+    let Some(swappable) = &args.swappable else {
+        return Ok(None);
+    };
+    let Func { attrs, vis, sig, feature_quote, wrapper_feature_quote, fallback_feature_quote, .. } = func;
+    let syn::Signature { fn_token, ident, generics, inputs, variadic, output, .. } = sig;
+    if let Mode::Export = func.mode {
+        return Err(syn::Error::new(swappable.span(), "`swappable` can only be used on imported functions"));
+    }
+    if args.import_style.is_some() {
+        return Err(syn::Error::new(swappable.span(), "`swappable` functions already are function items, `import_style` cannot be used"));
+    }
+    if let Some(param) = generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "`swappable` cannot be used on generic functions"));
+    }
+    if let Some(variadic) = variadic {
+        return Err(syn::Error::new_spanned(variadic, "variadic arguments cannot be swapped"));
+    }
+
+    let link_name = args.name.clone().unwrap_or_else(|| syn::LitStr::new(&ident.unraw().to_string(), ident.span()));
+    let import_ident = quote::format_ident!("__reprfn_{}_import", ident);
+    let static_ident = quote::format_ident!("{}_PTR", ident.unraw().to_string().to_uppercase());
+    let setter_ident = quote::format_ident!("set_{}", ident);
+    let params::Forward { inputs: forward_inputs, args: forward_args } = params::forward(inputs)?;
+    let Quotes { abi: abi_quote, then: then_quote, cold: cold_quote, ffi: ffi_quote, link: link_quote, require_link: require_link_quote, unsafe_block: unsafe_block_quote, safe: safe_quote } = quotes;
+    let arg_types = forward_inputs.iter().filter_map(|input| match input {
+        syn::FnArg::Typed(arg) => Some(&arg.ty),
+        syn::FnArg::Receiver(_) => None,
+    });
+    let fn_type = quote::quote! { unsafe #abi_quote fn(#(#arg_types),*) #output };
+    let wrapper_unsafety = if args.safe { quote::quote! {} } else { quote::quote! { unsafe } };
+    let setter_doc = format!("Replaces the implementation of [`{}`], `None` restores the linked `{}`", ident, link_name.value());
+
+    // Without the declaration, calls that were not redirected have nothing to fall back on
+    let fallback_quote = match (&args.feature_target, fallback_feature_quote) {
+        (FeatureTarget::Export(_), Some(fallback_feature_quote)) => {
+            let message = format!("`{}` is not linked with this feature set, replace it with `{}` first", link_name.value(), setter_ident);
+            quote::quote! {
+                #fallback_feature_quote
+                #[allow(dead_code, unused_variables)]
+                unsafe #fn_token #import_ident(#forward_inputs) #output {
+                    ::core::panic!(#message)
+                }
+            }
+        },
+        _ => quote::quote! {},
+    };
+    Ok(Some(quote::quote! {
+        #link_quote
+        #unsafe_block_quote #abi_quote {
+            #feature_quote
+            #[link_name = #link_name]
+            #[allow(dead_code)]
+            #ffi_quote
+            #safe_quote #fn_token #import_ident(#forward_inputs) #output;
+        }
+        #fallback_quote
+        #wrapper_feature_quote
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        static #static_ident: ::core::sync::atomic::AtomicPtr<()> = ::core::sync::atomic::AtomicPtr::new(::core::ptr::null_mut());
+        #then_quote
+        #(#attrs)*
+        #wrapper_feature_quote
+        #cold_quote
+        #[inline]
+        #vis #wrapper_unsafety #fn_token #ident(#forward_inputs) #output {
+            let swapped = #static_ident.load(::core::sync::atomic::Ordering::Acquire);
+            if swapped.is_null() {
+                #[allow(unused_unsafe)]
+                return unsafe { #import_ident(#(#forward_args),*) };
+            }
+            // Only the setter stores this private pointer, always from a function of this signature
+            let swapped = unsafe { ::core::mem::transmute::<*mut (), #fn_type>(swapped) };
+            unsafe { swapped(#(#forward_args),*) }
+        }
+        #[doc = #setter_doc]
+        ///
+        /// # Safety
+        ///
+        /// The replacement is called in place of the import, also from safe callers of a `safe` import, and must
+        /// uphold its contract.
+        #wrapper_feature_quote
+        #[allow(dead_code)]
+        #vis unsafe fn #setter_ident(replacement: ::core::option::Option<#fn_type>) {
+            let replacement = match replacement {
+                Some(replacement) => replacement as *mut (),
+                None => ::core::ptr::null_mut(),
+            };
+            #static_ident.store(replacement, ::core::sync::atomic::Ordering::Release);
+        }
+        #require_link_quote
+    }))
+}
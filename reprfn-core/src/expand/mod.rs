@@ -0,0 +1,1726 @@
+//! The expansion behind the `reprfn` macros, for proc macros generating the same output
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::ToTokens;
+use syn::ext::IdentExt;
+
+mod body;
+mod convention;
+mod defaults;
+mod header;
+mod hook;
+mod layout;
+mod link;
+mod mono;
+mod params;
+mod section;
+mod status;
+mod target;
+#[cfg(test)]
+mod tests;
+mod traits;
+mod types;
+mod vtable;
+
+use crate::ABIS;
+
+fn validate_abi(abi: syn::LitStr) -> Result<syn::LitStr, syn::Error> {
+    let raw = abi.value();
+    if crate::is_valid_abi(&raw) {
+        Ok(abi)
+    } else {
+        let span = abi.span();
+        let suggestion = traits::closest(&raw, &ABIS).map(|closest| format!(" (did you mean `{}`?)", closest)).unwrap_or_default();
+        Err(syn::Error::new(span, format!("Invalid ABI '{}'{}, expecting one of:{}", raw, suggestion, traits::table())))
+    }
+}
+
+/// Rejects symbol names rustc would only fail on with an obscure error, like embedded NULs
+fn validate_symbol_name(name: &syn::LitStr) -> syn::Result<()> {
+    validate_symbol(name, "symbol names")
+}
+
+/// `what` names the rejected value in the error, a prefix is checked like the symbol it is part of
+fn validate_symbol(value: &syn::LitStr, what: &str) -> syn::Result<()> {
+    let raw = value.value();
+    if raw.is_empty() {
+        return Err(syn::Error::new(value.span(), format!("{} cannot be empty", what)));
+    }
+    match raw.chars().find(|c| c.is_control() || c.is_whitespace()) {
+        Some('\0') => Err(syn::Error::new(value.span(), format!("{} cannot contain NUL bytes", what))),
+        Some(c) if c.is_control() => Err(syn::Error::new(value.span(), format!("{} cannot contain control characters, found {:?}", what, c))),
+        Some(c) => Err(syn::Error::new(value.span(), format!("{} cannot contain whitespace, found {:?}", what, c))),
+        None => Ok(()),
+    }
+}
+
+/// ELF version nodes, like `LIB_1.0` or `GLIBC_2.2.5`
+fn is_version_node(node: &str) -> bool {
+    node.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && node.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// `sym@VERSION` references a symbol version, the dynamic linker rejects anything else with an `@` in it
+fn validate_symbol_version(name: &syn::LitStr) -> syn::Result<()> {
+    let raw = name.value();
+    let Some((symbol, version)) = raw.split_once('@') else {
+        return Ok(());
+    };
+    if let Some(version) = version.strip_prefix('@') {
+        return Err(syn::Error::new(name.span(), format!("'{}' names the default version a library defines, imports reference a version as '{}@{}'", raw, symbol, version)));
+    }
+    if symbol.is_empty() || !is_version_node(version) {
+        return Err(syn::Error::new(name.span(), format!("invalid versioned symbol '{}', expecting a name like 'memcpy@GLIBC_2.2.5'", raw)));
+    }
+    Ok(())
+}
+
+fn parse_str_list(input: syn::parse::ParseStream) -> syn::Result<Vec<syn::LitStr>> {
+    let content;
+    syn::bracketed!(content in input);
+    let list = content.parse_terminated(|input| input.parse::<syn::LitStr>(), syn::Token![,])?;
+    Ok(list.into_iter().collect())
+}
+
+/// The sanitizers `#[no_sanitize]` knows about
+const SANITIZERS: [&str; 8] = ["address", "cfi", "hwaddress", "kcfi", "memory", "memtag", "shadow_call_stack", "thread"];
+
+/// The options `#[reprfn]` accepts, unknown ones are matched against them for a suggestion
+const OPTIONS: [&str; 68] = [
+    "abi", "name", "mode", "feature", "link", "require_link", "then", "accessor", "keep_extern", "validate_only",
+    "tuple_return", "safe", "module", "uefi", "multiversion", "import_name", "override_abi", "ctor", "dtor",
+    "instances", "cold", "assert_params", "debug_trace", "return_via_ptr", "cfg", "naked", "import_style",
+    "status_enum", "since", "emit_header", "default_version", "panic_hook", "pointer_width", "track_caller",
+    "string_return", "no_mangle", "result_abi", "bare", "ordinal", "check_only", "suggest_ffi_types", "errno",
+    "underscore_alias", "deep_abi", "anon", "no_sanitize", "ffi_pure", "ffi_const", "prefix", "unstable_attr",
+    "swappable", "naked_interrupt", "from_name_convention", "assert_abi_layout", "repr_c_check", "module_prefix",
+    "hash_suffix", "register_in", "hidden", "wasm_bindgen", "doc", "link_name", "reexport_as", "rust_wrapper", "dedup",
+    "opaque", "internal_abi", "inline",
+];
+
+/// `{ export = "build-lib", import = "build-app" }`, both modes are required
+fn parse_mode_features(input: syn::parse::ParseStream) -> syn::Result<(syn::LitStr, syn::LitStr)> {
+    let content;
+    let braces = syn::braced!(content in input);
+
+    let (mut export, mut import) = (None, None);
+    while !content.is_empty() {
+        let key: syn::Ident = content.parse()?;
+        content.parse::<syn::Token![=]>()?;
+        let value: syn::LitStr = content.parse()?;
+        let slot = if key == "export" {
+            &mut export
+        } else if key == "import" {
+            &mut import
+        } else {
+            return Err(syn::Error::new(key.span(), format!("invalid mode '{}', expecting one of '['export', 'import']'", key)));
+        };
+        if slot.replace(value).is_some() {
+            return Err(syn::Error::new(key.span(), format!("duplicate mode '{}'", key)));
+        }
+
+        if !content.is_empty() {
+            content.parse::<syn::Token![,]>()?;
+        }
+    }
+    match (export, import) {
+        (Some(export), Some(import)) => Ok((export, import)),
+        _ => Err(syn::Error::new(braces.span.join(), "a mode per feature needs both an `export` and an `import` feature")),
+    }
+}
+
+/// `{ on = "ffi", applies_to = "wrapper" }`, the feature and the generated items it gates
+fn parse_feature_target(input: syn::parse::ParseStream) -> syn::Result<(syn::LitStr, FeatureTarget)> {
+    let content;
+    let braces = syn::braced!(content in input);
+
+    let (mut on, mut applies_to) = (None, None);
+    while !content.is_empty() {
+        let key: syn::Ident = content.parse()?;
+        content.parse::<syn::Token![=]>()?;
+        let value: syn::LitStr = content.parse()?;
+        let slot = if key == "on" {
+            &mut on
+        } else if key == "applies_to" {
+            &mut applies_to
+        } else {
+            return Err(syn::Error::new(key.span(), format!("invalid key '{}', expecting one of '['on', 'applies_to']'", key)));
+        };
+        if slot.replace(value).is_some() {
+            return Err(syn::Error::new(key.span(), format!("duplicate key '{}'", key)));
+        }
+
+        if !content.is_empty() {
+            content.parse::<syn::Token![,]>()?;
+        }
+    }
+    let (Some(on), Some(applies_to)) = (on, applies_to) else {
+        return Err(syn::Error::new(braces.span.join(), "a feature per item needs both the feature `on` and the item it `applies_to`"));
+    };
+    let target = match applies_to.value().as_str() {
+        "export" => FeatureTarget::Export(applies_to),
+        "wrapper" => FeatureTarget::Wrapper(applies_to),
+        "all" => FeatureTarget::All,
+        raw => {
+            return Err(syn::Error::new(applies_to.span(), format!("invalid item '{}', expecting one of '['export', 'wrapper', 'all']'", raw)));
+        },
+    };
+    Ok((on, target))
+}
+
+/// 32-bit FNV-1a cut to six hex digits, stable across compiler versions unlike `std::hash`
+fn package_hash(package: &str) -> String {
+    let hash = package.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ u32::from(byte)).wrapping_mul(0x01000193));
+    format!("{:06x}", hash & 0xff_ffff)
+}
+
+/// The visibility of an item moved one module down, seen from the scope it was declared in as before
+fn nested_vis(vis: &syn::Visibility) -> syn::Visibility {
+    match vis {
+        syn::Visibility::Inherited => syn::parse_quote! { pub(super) },
+        // `self` and `super` are relative to the new module, `crate` and `in crate::..` stay as they are
+        syn::Visibility::Restricted(restricted) => {
+            let path = &restricted.path;
+            match path.segments.first() {
+                Some(segment) if segment.ident == "self" => {
+                    let rest = path.segments.iter().skip(1);
+                    syn::parse_quote! { pub(in super #(:: #rest)*) }
+                },
+                Some(segment) if segment.ident == "super" => syn::parse_quote! { pub(in super::#path) },
+                _ => vis.clone(),
+            }
+        },
+        vis => vis.clone(),
+    }
+}
+
+fn find_attr<'a>(attrs: &'a [syn::Attribute], name: &str) -> Option<&'a syn::Attribute> {
+    attrs.iter().find(|attr| {
+        if attr.path().is_ident(name) {
+            return true;
+        }
+        // `#[unsafe(no_mangle)]` and friends
+        attr.path().is_ident("unsafe") && attr.parse_args::<syn::Meta>().is_ok_and(|meta| meta.path().is_ident(name))
+    })
+}
+
+fn feature_quote(feature: Option<&syn::LitStr>, cfg: Option<&syn::Meta>, pointer_width: Option<&syn::LitStr>) -> TokenStream2 {
+    let mut cfg_quote = match cfg {
+        Some(cfg) => quote::quote! { #[cfg(#cfg)] },
+        None => quote::quote! {},
+    };
+    if let Some(pointer_width) = pointer_width {
+        cfg_quote.extend(quote::quote! { #[cfg(target_pointer_width = #pointer_width)] });
+    }
+    let Some(feature_value) = feature else {
+        return cfg_quote;
+    };
+
+    // A leading `!` gates on the feature being disabled
+    if let Some(negated) = feature_value.value().strip_prefix('!') {
+        let negated = syn::LitStr::new(negated, feature_value.span());
+        quote::quote! {
+            #[cfg(not(feature = #negated))]
+            #cfg_quote
+        }
+    } else {
+        quote::quote! {
+            #[cfg(feature = #feature_value)]
+            #cfg_quote
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Abi {
+    /// No `abi` attribute was given
+    Unspecified,
+    /// An ABI was named explicitly
+    Explicit(syn::LitStr),
+    /// The ABI was suppressed with `abi = "none"`
+    None,
+}
+
+impl Abi {
+    fn lit(&self) -> Option<&syn::LitStr> {
+        match self {
+            Abi::Explicit(lit) => Some(lit),
+            _ => None,
+        }
+    }
+
+    fn is_explicit(&self) -> bool {
+        !matches!(self, Abi::Unspecified)
+    }
+
+    fn value(&self) -> Option<String> {
+        self.lit().map(syn::LitStr::value)
+    }
+
+    /// Without an `abi` the function keeps the Rust ABI and is treated exactly like `abi = "Rust"`
+    fn is_rust(&self) -> bool {
+        !self.is_explicit() || self.value().as_deref().is_some_and(traits::is_rust)
+    }
+}
+
+#[derive(Clone)]
+enum Inline {
+    Always,
+    Never,
+    Hint,
+}
+
+#[derive(Clone)]
+enum Mode {
+    Export,
+    Import,
+}
+
+#[derive(Clone)]
+enum NoMangle {
+    Always,
+    /// Only without `debug_assertions`
+    Release,
+    /// Only with `debug_assertions`
+    Debug,
+    Never,
+}
+
+#[derive(Clone)]
+enum ImportStyle {
+    /// The function is declared directly in an extern block
+    Block,
+    /// A function item wraps a hidden declaration
+    Item,
+}
+
+/// The generated items `feature` gates
+#[derive(Clone)]
+enum FeatureTarget {
+    All,
+    /// Only the exported function or the extern declaration
+    Export(syn::LitStr),
+    /// Only the Rust functions wrapping an import
+    Wrapper(syn::LitStr),
+}
+
+#[derive(Clone)]
+struct Args {
+    abi: Abi,
+    abi_targets: Option<target::Targeted>,
+    name: Option<syn::LitStr>,
+    name_targets: Option<target::Targeted>,
+    feature: Option<syn::LitStr>,
+    feature_target: FeatureTarget,
+    cfg: Option<syn::Meta>,
+    pointer_width: Option<syn::LitStr>,
+    link: Option<link::Link>,
+    mode: Option<Mode>,
+    require_link: bool,
+    then: Option<syn::Path>,
+    accessor: Option<syn::Ident>,
+    keep_extern: bool,
+    validate_only: bool,
+    tuple_return: Option<syn::Ident>,
+    safe: bool,
+    module: Option<syn::Ident>,
+    uefi: bool,
+    multiversion: Option<Vec<syn::LitStr>>,
+    forward: bool,
+    import_name: Option<syn::LitStr>,
+    override_abi: bool,
+    ctor: bool,
+    dtor: bool,
+    instances: Option<Vec<syn::LitStr>>,
+    cold: bool,
+    inline: Option<(Inline, syn::LitStr)>,
+    assert_params: Option<syn::LitInt>,
+    debug_trace: bool,
+    return_via_ptr: bool,
+    naked: bool,
+    import_style: Option<(ImportStyle, syn::LitStr)>,
+    status_enum: Option<status::StatusEnum>,
+    since: Option<syn::LitStr>,
+    emit_header: bool,
+    default_version: Option<syn::LitStr>,
+    panic_hook: bool,
+    track_caller: Option<syn::LitBool>,
+    string_return: Option<syn::LitStr>,
+    no_mangle: Option<(NoMangle, syn::LitStr)>,
+    result_abi: Option<syn::LitBool>,
+    bare: Option<syn::LitBool>,
+    ordinal: Option<syn::LitInt>,
+    check_only: bool,
+    suggest_ffi_types: bool,
+    errno: Option<(syn::Path, proc_macro2::Span)>,
+    underscore_alias: Option<syn::LitBool>,
+    deep_abi: Option<syn::LitBool>,
+    anon: Option<syn::LitBool>,
+    no_sanitize: Vec<syn::LitStr>,
+    ffi_pure: Option<syn::LitBool>,
+    ffi_const: Option<syn::LitBool>,
+    prefix: Option<syn::LitStr>,
+    unstable_attr: Option<syn::Path>,
+    swappable: Option<syn::LitBool>,
+    naked_interrupt: Option<syn::LitBool>,
+    from_name_convention: Option<(convention::Convention, syn::LitStr)>,
+    assert_abi_layout: Option<layout::Layout>,
+    /// `mode = { export = "...", import = "..." }`, the features selecting each mode
+    mode_features: Option<(syn::LitStr, syn::LitStr)>,
+    repr_c_check: Option<syn::LitBool>,
+    module_prefix: Option<syn::LitStr>,
+    hash_suffix: Option<syn::LitBool>,
+    register_in: Option<syn::LitStr>,
+    hidden: bool,
+    wasm_bindgen: Option<syn::LitBool>,
+    doc: Vec<syn::LitStr>,
+    link_name: Option<syn::LitStr>,
+    reexport_as: Option<syn::Ident>,
+    rust_wrapper: Option<syn::LitBool>,
+    dedup: Option<syn::LitBool>,
+    opaque: Option<syn::LitBool>,
+    internal_abi: Option<syn::LitBool>,
+}
+
+impl Args {
+    /// Imports called through a Rust function item rather than declared directly
+    fn is_wrapped(&self) -> bool {
+        matches!(self.import_style, Some((ImportStyle::Item, _))) || self.swappable.is_some()
+    }
+
+    fn new() -> Self {
+        Args {
+            abi: Abi::Unspecified,
+            abi_targets: None,
+            name: None,
+            name_targets: None,
+            feature: None,
+            feature_target: FeatureTarget::All,
+            cfg: None,
+            pointer_width: None,
+            link: None,
+            mode: None,
+            require_link: false,
+            then: None,
+            accessor: None,
+            keep_extern: false,
+            validate_only: false,
+            tuple_return: None,
+            safe: false,
+            module: None,
+            uefi: false,
+            multiversion: None,
+            forward: false,
+            import_name: None,
+            override_abi: false,
+            ctor: false,
+            dtor: false,
+            instances: None,
+            cold: false,
+            inline: None,
+            assert_params: None,
+            debug_trace: false,
+            return_via_ptr: false,
+            naked: false,
+            import_style: None,
+            status_enum: None,
+            since: None,
+            emit_header: false,
+            default_version: None,
+            panic_hook: false,
+            track_caller: None,
+            string_return: None,
+            no_mangle: None,
+            result_abi: None,
+            bare: None,
+            ordinal: None,
+            check_only: false,
+            suggest_ffi_types: false,
+            errno: None,
+            underscore_alias: None,
+            deep_abi: None,
+            anon: None,
+            no_sanitize: Vec::new(),
+            ffi_pure: None,
+            ffi_const: None,
+            prefix: None,
+            unstable_attr: None,
+            swappable: None,
+            naked_interrupt: None,
+            from_name_convention: None,
+            assert_abi_layout: None,
+            mode_features: None,
+            repr_c_check: None,
+            module_prefix: None,
+            hash_suffix: None,
+            register_in: None,
+            hidden: false,
+            wasm_bindgen: None,
+            doc: Vec::new(),
+            link_name: None,
+            reexport_as: None,
+            rust_wrapper: None,
+            dedup: None,
+            opaque: None,
+            internal_abi: None,
+        }
+    }
+
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("abi") {
+            let input = meta.value()?;
+            if input.peek(syn::token::Brace) {
+                let abi_targets = target::Targeted::parse(input)?;
+                for (_, value) in abi_targets.predicates() {
+                    validate_abi(value.clone())?;
+                }
+                self.abi = Abi::Unspecified;
+                self.abi_targets = Some(abi_targets);
+                return Ok(());
+            }
+            let value = validate_abi(input.parse()?)?;
+            self.abi_targets = None;
+            self.abi = if value.value() == "none" {
+                Abi::None
+            } else {
+                Abi::Explicit(value)
+            };
+        } else if meta.path.is_ident("name") {
+            let input = meta.value()?;
+            if input.peek(syn::token::Brace) {
+                let targeted = target::Targeted::parse(input)?;
+                for (_, name) in targeted.predicates() {
+                    validate_symbol_name(name)?;
+                }
+                self.name = None;
+                self.name_targets = Some(targeted);
+                return Ok(());
+            }
+            let value: syn::LitStr = input.parse()?;
+            validate_symbol_name(&value)?;
+            self.name_targets = None;
+            self.name = if value.value() == "none" {
+                None
+            } else {
+                Some(value)
+            };
+        } else if meta.path.is_ident("mode") {
+            let input = meta.value()?;
+            if input.peek(syn::token::Brace) {
+                self.mode = None;
+                self.forward = false;
+                self.mode_features = Some(parse_mode_features(input)?);
+                return Ok(());
+            }
+            let value: syn::LitStr = input.parse()?;
+            self.mode_features = None;
+            self.forward = value.value() == "forward";
+            self.mode = if value.value() == "none" {
+                None
+            } else if value.value() == "import" {
+                Some(Mode::Import)
+            } else if value.value() == "export" || value.value() == "forward" {
+                Some(Mode::Export)
+            } else {
+                return Err(meta.error(format!("invalid mode '{}', expecting one of '['none', 'import', 'export', 'forward']'", value.value())));
+            }
+        } else if meta.path.is_ident("feature") {
+            let input = meta.value()?;
+            let value = if input.peek(syn::token::Brace) {
+                let (on, target) = parse_feature_target(input)?;
+                self.feature_target = target;
+                on
+            } else {
+                self.feature_target = FeatureTarget::All;
+                input.parse::<syn::LitStr>()?
+            };
+            let raw = value.value();
+            let feature = raw.strip_prefix('!').unwrap_or(&raw);
+            if feature.is_empty() || feature.contains('!') {
+                return Err(syn::Error::new(value.span(), format!("invalid feature '{}', expecting a feature name optionally prefixed by a single '!'", raw)));
+            }
+            self.feature = if value.value() == "none" {
+                None
+            } else {
+                Some(value)
+            };
+        } else if meta.path.is_ident("link") {
+            self.link = link::Link::parse(meta.value()?)?;
+        } else if meta.path.is_ident("require_link") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.require_link = value.value;
+        } else if meta.path.is_ident("then") {
+            self.then = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("accessor") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.accessor = Some(value.parse()?);
+        } else if meta.path.is_ident("keep_extern") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.keep_extern = value.value;
+        } else if meta.path.is_ident("validate_only") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.validate_only = value.value;
+        } else if meta.path.is_ident("tuple_return") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.tuple_return = Some(value.parse()?);
+        } else if meta.path.is_ident("safe") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.safe = value.value;
+        } else if meta.path.is_ident("module") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.module = Some(value.parse()?);
+        } else if meta.path.is_ident("uefi") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.uefi = value.value;
+        } else if meta.path.is_ident("multiversion") {
+            self.multiversion = Some(parse_str_list(meta.value()?)?);
+        } else if meta.path.is_ident("import_name") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            validate_symbol_name(&value)?;
+            self.import_name = Some(value);
+        } else if meta.path.is_ident("override_abi") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.override_abi = value.value;
+        } else if meta.path.is_ident("ctor") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.ctor = value.value;
+        } else if meta.path.is_ident("dtor") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.dtor = value.value;
+        } else if meta.path.is_ident("instances") {
+            self.instances = Some(parse_str_list(meta.value()?)?);
+        } else if meta.path.is_ident("cold") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.cold = value.value;
+        } else if meta.path.is_ident("assert_params") {
+            let value: syn::LitInt = meta.value()?.parse()?;
+            value.base10_parse::<usize>()?;
+            self.assert_params = Some(value);
+        } else if meta.path.is_ident("debug_trace") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.debug_trace = value.value;
+        } else if meta.path.is_ident("return_via_ptr") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.return_via_ptr = value.value;
+        } else if meta.path.is_ident("cfg") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            // Any well-formed predicate is forwarded as is, so unstable ones like `version(..)` work too
+            self.cfg = if value.value() == "none" {
+                None
+            } else {
+                Some(value.parse()?)
+            };
+        } else if meta.path.is_ident("naked") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.naked = value.value;
+        } else if meta.path.is_ident("import_style") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.import_style = match value.value().as_str() {
+                "none" => None,
+                "block" => Some((ImportStyle::Block, value)),
+                "item" => Some((ImportStyle::Item, value)),
+                _ => return Err(meta.error(format!("invalid import_style '{}', expecting one of '['none', 'block', 'item']'", value.value()))),
+            };
+        } else if meta.path.is_ident("status_enum") {
+            self.status_enum = Some(status::StatusEnum::parse(meta.value()?)?);
+        } else if meta.path.is_ident("since") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.since = if value.value() == "none" {
+                None
+            } else {
+                Some(value)
+            };
+        } else if meta.path.is_ident("emit_header") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.emit_header = value.value;
+        } else if meta.path.is_ident("default_version") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            let raw = value.value();
+            if raw == "none" {
+                self.default_version = None;
+                return Ok(());
+            }
+            if !is_version_node(&raw) {
+                return Err(syn::Error::new(value.span(), format!("invalid version node '{}', expecting a name like 'LIB_1.0'", raw)));
+            }
+            self.default_version = Some(value);
+        } else if meta.path.is_ident("panic_hook") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.panic_hook = value.value;
+        } else if meta.path.is_ident("pointer_width") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.pointer_width = match value.value().as_str() {
+                "none" => None,
+                "16" | "32" | "64" => Some(value),
+                _ => return Err(meta.error(format!("invalid pointer_width '{}', expecting one of '['none', '16', '32', '64']'", value.value()))),
+            };
+        } else if meta.path.is_ident("track_caller") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.track_caller = value.value.then_some(value);
+        } else if meta.path.is_ident("string_return") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.string_return = match value.value().as_str() {
+                "none" => None,
+                "cstring" => Some(value),
+                _ => return Err(meta.error(format!("invalid string_return '{}', expecting one of '['none', 'cstring']'", value.value()))),
+            };
+        } else if meta.path.is_ident("no_mangle") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.no_mangle = match value.value().as_str() {
+                "none" => None,
+                "always" => Some((NoMangle::Always, value)),
+                "release" => Some((NoMangle::Release, value)),
+                "debug" => Some((NoMangle::Debug, value)),
+                "never" => Some((NoMangle::Never, value)),
+                _ => return Err(meta.error(format!("invalid no_mangle '{}', expecting one of '['none', 'always', 'release', 'debug', 'never']'", value.value()))),
+            };
+        } else if meta.path.is_ident("result_abi") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.result_abi = value.value.then_some(value);
+        } else if meta.path.is_ident("bare") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.bare = value.value.then_some(value);
+        } else if meta.path.is_ident("ordinal") {
+            let value: syn::LitInt = meta.value()?.parse()?;
+            value.base10_parse::<u16>()?;
+            self.ordinal = Some(value);
+        } else if meta.path.is_ident("check_only") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.check_only = value.value;
+        } else if meta.path.is_ident("suggest_ffi_types") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.suggest_ffi_types = value.value;
+        } else if meta.path.is_ident("errno") {
+            let input = meta.value()?;
+            // `true` stores the code for `reprfn_core::last_error`, a path names a setter of its own
+            self.errno = if input.peek(syn::LitBool) {
+                let value: syn::LitBool = input.parse()?;
+                value.value.then(|| (syn::parse_quote! { ::reprfn_core::set_last_error }, value.span()))
+            } else {
+                let value: syn::LitStr = input.parse()?;
+                Some((value.parse()?, value.span()))
+            };
+        } else if meta.path.is_ident("underscore_alias") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.underscore_alias = value.value.then_some(value);
+        } else if meta.path.is_ident("deep_abi") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.deep_abi = value.value.then_some(value);
+        } else if meta.path.is_ident("anon") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.anon = value.value.then_some(value);
+        } else if meta.path.is_ident("no_sanitize") {
+            let value = meta.value()?;
+            let sanitizers = if value.peek(syn::LitStr) { vec![value.parse()?] } else { parse_str_list(value)? };
+            for sanitizer in &sanitizers {
+                if !SANITIZERS.contains(&sanitizer.value().as_str()) {
+                    let message = format!("invalid sanitizer '{}', expecting one of '{:?}'", sanitizer.value(), SANITIZERS);
+                    return Err(syn::Error::new(sanitizer.span(), message));
+                }
+            }
+            self.no_sanitize = sanitizers;
+        } else if meta.path.is_ident("ffi_pure") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.ffi_pure = value.value.then_some(value);
+        } else if meta.path.is_ident("ffi_const") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.ffi_const = value.value.then_some(value);
+        } else if meta.path.is_ident("prefix") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            validate_symbol(&value, "prefixes")?;
+            self.prefix = Some(value);
+        } else if meta.path.is_ident("unstable_attr") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.unstable_attr = if value.value() == "none" {
+                None
+            } else {
+                Some(value.parse().map_err(|_| syn::Error::new(value.span(), format!("invalid unstable_attr '{}', expecting an attribute name", value.value())))?)
+            };
+        } else if meta.path.is_ident("swappable") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.swappable = value.value.then_some(value);
+        } else if meta.path.is_ident("naked_interrupt") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.naked_interrupt = value.value.then_some(value);
+        } else if meta.path.is_ident("from_name_convention") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.from_name_convention = match convention::Convention::parse(&value.value()) {
+                _ if value.value() == "none" => None,
+                Some(convention) => Some((convention, value)),
+                None => {
+                    let message = format!("invalid from_name_convention '{}', expecting one of '['none', 'camelCase', 'PascalCase', 'SCREAMING_SNAKE_CASE', 'LibCamel']'", value.value());
+                    return Err(meta.error(message));
+                },
+            };
+        } else if meta.path.is_ident("assert_abi_layout") {
+            self.assert_abi_layout = Some(layout::Layout::parse(meta.value()?)?);
+        } else if meta.path.is_ident("repr_c_check") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.repr_c_check = value.value.then_some(value);
+        } else if meta.path.is_ident("module_prefix") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.module_prefix = if value.value() == "none" { None } else { Some(value) };
+        } else if meta.path.is_ident("hash_suffix") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.hash_suffix = value.value.then_some(value);
+        } else if meta.path.is_ident("register_in") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.register_in = if value.value() == "none" {
+                None
+            } else {
+                section::validate(&value)?;
+                Some(value)
+            };
+        } else if meta.path.is_ident("hidden") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.hidden = value.value;
+        } else if meta.path.is_ident("wasm_bindgen") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.wasm_bindgen = value.value.then_some(value);
+        } else if meta.path.is_ident("doc") {
+            // Every `doc` is a line of its own, in the order given
+            self.doc.push(meta.value()?.parse()?);
+        } else if meta.path.is_ident("link_name") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            validate_symbol_name(&value)?;
+            self.link_name = if value.value() == "none" {
+                None
+            } else {
+                Some(value)
+            };
+        } else if meta.path.is_ident("reexport_as") {
+            let value: syn::Path = meta.value()?.parse()?;
+            let Some(rename) = value.get_ident() else {
+                return Err(syn::Error::new_spanned(value, "`reexport_as` takes the single name to re-export the function as"));
+            };
+            self.reexport_as = Some(rename.clone());
+        } else if meta.path.is_ident("rust_wrapper") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.rust_wrapper = value.value.then_some(value);
+        } else if meta.path.is_ident("dedup") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.dedup = value.value.then_some(value);
+        } else if meta.path.is_ident("opaque") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.opaque = value.value.then_some(value);
+        } else if meta.path.is_ident("internal_abi") {
+            let value: syn::LitBool = meta.value()?.parse()?;
+            self.internal_abi = value.value.then_some(value);
+        } else if meta.path.is_ident("inline") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.inline = match value.value().as_str() {
+                "none" => None,
+                "always" => Some((Inline::Always, value)),
+                "never" => Some((Inline::Never, value)),
+                "hint" => Some((Inline::Hint, value)),
+                _ => return Err(meta.error(format!("invalid inline '{}', expecting one of '['none', 'always', 'never', 'hint']'", value.value()))),
+            };
+        } else {
+            let key = meta.path.to_token_stream().to_string().replace(' ', "");
+            let suggestion = traits::closest(&key, &OPTIONS).map(|closest| format!(" (did you mean `{}`?)", closest)).unwrap_or_default();
+            return Err(meta.error(format!("unsupported reprfn property '{}'{}", key, suggestion)));
+        }
+        Ok(())
+    }
+}
+
+/// Expands `#[reprfn(attr)] item`, the errors included as `compile_error!` invocations
+pub fn reprfn(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
+    match expand(attr, item) {
+        Ok(expanded) => expanded,
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+fn expand(attr: TokenStream2, item: TokenStream2) -> syn::Result<TokenStream2> {
+    let args = parse_args(attr)?;
+    let input = syn::parse2::<syn::Item>(item)?;
+    expand_with(args, input)
+}
+
+fn parse_args(attr: TokenStream2) -> syn::Result<Args> {
+    let mut args = Args::new();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    syn::parse::Parser::parse2(parser, attr)?;
+    Ok(args)
+}
+
+fn expand_with(args: Args, input: syn::Item) -> syn::Result<TokenStream2> {
+    // Validation still runs in full, only its output is thrown away
+    let original = args.validate_only.then(|| input.clone());
+
+    let expanded = expand_item(args, input)?;
+    Ok(match original {
+        Some(original) => original.into_token_stream(),
+        None => expanded,
+    })
+}
+
+fn expand_item(args: Args, input: syn::Item) -> syn::Result<TokenStream2> {
+    match input {
+        syn::Item::Fn(input) => expand_fn(args, input),
+        syn::Item::ForeignMod(input) => expand_foreign_mod(args, input),
+        syn::Item::Static(input) => expand_static(args, input),
+        syn::Item::Trait(input) => expand_trait(args, input),
+        // `fn name();` is not an item on its own, it is imported like a function with an empty body
+        syn::Item::Verbatim(tokens) => match syn::parse2::<syn::ForeignItemFn>(tokens.clone()) {
+            Ok(syn::ForeignItemFn { attrs, vis, sig, .. }) => {
+                let block = Box::new(syn::Block { brace_token: Default::default(), stmts: Vec::new() });
+                expand_fn(args, syn::ItemFn { attrs, vis, sig, block })
+            },
+            // `static NAME: T;` has no value to export, it is imported
+            Err(_) => match syn::parse2::<syn::ForeignItemStatic>(tokens.clone()) {
+                Ok(syn::ForeignItemStatic { attrs, vis, static_token, mutability, ident, colon_token, ty, semi_token }) => {
+                    if let Some(Mode::Export) = args.mode {
+                        return Err(syn::Error::new_spanned(ident, "exported statics need a value"));
+                    }
+                    // Extern blocks only take the type, the value is never emitted
+                    let expr = Box::new(syn::parse_quote! { () });
+                    let input = syn::ItemStatic { attrs, vis, static_token, mutability, ident, colon_token, ty, eq_token: Default::default(), expr, semi_token };
+                    expand_static(Args { mode: Some(Mode::Import), ..args }, input)
+                },
+                Err(_) => Err(syn::Error::new_spanned(tokens, "reprfn can only be applied to functions, statics, extern blocks and traits")),
+            },
+        },
+        input => Err(syn::Error::new_spanned(input, "reprfn can only be applied to functions, statics, extern blocks and traits")),
+    }
+}
+
+/// Expands `#[derive(ReprC)]`
+pub fn derive_repr_c(item: TokenStream2) -> TokenStream2 {
+    let input = match syn::parse2::<syn::DeriveInput>(item) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error(),
+    };
+    let ints = ["u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize"];
+
+    let mut reprs = Vec::new();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        let parsed = attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                reprs.push(ident.to_string());
+            }
+            // `align(8)` and `packed(2)` do not change whether the layout is C
+            if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream2>()?;
+            }
+            Ok(())
+        });
+        if let Err(err) = parsed {
+            return err.to_compile_error();
+        }
+    }
+    let repr_c = reprs.iter().any(|repr| match &input.data {
+        syn::Data::Enum(_) => repr == "C" || ints.contains(&repr.as_str()),
+        _ => repr == "C" || repr == "transparent",
+    });
+    if !repr_c {
+        let message = match &input.data {
+            syn::Data::Enum(_) => "`ReprC` enums need `#[repr(C)]` or an integer representation like `#[repr(u8)]`",
+            _ => "`ReprC` types need `#[repr(C)]` or `#[repr(transparent)]`",
+        };
+        return syn::Error::new_spanned(&input.ident, message).to_compile_error();
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote::quote! {
+        unsafe impl #impl_generics ::reprfn_core::ReprC for #ident #ty_generics #where_clause {}
+    }
+}
+
+/// Expands `section_slice!(input)`
+pub fn section_slice(input: TokenStream2) -> TokenStream2 {
+    match syn::parse2::<section::Slice>(input) {
+        Ok(slice) => slice.quote(),
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+/// Expands `reprfn_mono!(input)`
+pub fn reprfn_mono(input: TokenStream2) -> TokenStream2 {
+    match syn::parse2::<mono::Mono>(input).and_then(|mono| mono.quote()) {
+        Ok(expanded) => expanded,
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+/// Expands `reprfn_vtable!(input)`
+pub fn reprfn_vtable(input: TokenStream2) -> TokenStream2 {
+    match syn::parse2::<vtable::Vtable>(input).and_then(|vtable| vtable.quote()) {
+        Ok(expanded) => expanded,
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+/// Expands `#[reprfn_defaults(attr)] item`, storing the defaults for the expansions that follow
+pub fn reprfn_defaults(attr: TokenStream2, item: TokenStream2) -> TokenStream2 {
+    let mut defaults = defaults::Defaults::default();
+    let parser = syn::meta::parser(|meta| defaults::parse(meta, &mut defaults));
+
+    if let Err(err) = syn::parse::Parser::parse2(parser, attr) {
+        return err.to_compile_error();
+    }
+    defaults::set(defaults);
+    item
+}
+
+/// The export and the import, each under its feature, the export winning when both are enabled
+fn expand_mode_features(args: Args, (export, import): (syn::LitStr, syn::LitStr), input: syn::ItemFn) -> syn::Result<TokenStream2> {
+    if input.block.stmts.is_empty() {
+        return Err(syn::Error::new_spanned(&input.sig.ident, "a mode per feature needs the body of the export"));
+    }
+
+    let mut expanded = TokenStream2::new();
+    for (mode, predicate) in [
+        (Mode::Export, quote::quote! { feature = #export }),
+        (Mode::Import, quote::quote! { all(feature = #import, not(feature = #export)) }),
+    ] {
+        let mut mode_args = args.clone();
+        mode_args.mode = Some(mode);
+        mode_args.cfg = Some(match &args.cfg {
+            Some(cfg) => syn::parse_quote! { all(#cfg, #predicate) },
+            None => syn::parse_quote! { #predicate },
+        });
+        expanded.extend(expand_fn(mode_args, input.clone())?);
+    }
+    Ok(expanded)
+}
+
+/// The C export everywhere but on `wasm32`, where wasm-bindgen exports the plain Rust function instead
+fn expand_wasm_bindgen(args: Args, wasm_bindgen: syn::LitBool, input: syn::ItemFn) -> syn::Result<TokenStream2> {
+    if input.block.stmts.is_empty() || matches!(args.mode, Some(Mode::Import)) {
+        return Err(syn::Error::new(wasm_bindgen.span(), "`wasm_bindgen` can only be used on exported functions"));
+    }
+    if let Some(param) = input.sig.generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "wasm-bindgen cannot export generic functions"));
+    }
+
+    // wasm-bindgen converts the parameters itself, the hints only apply to the C export
+    let mut wasm_fn = input.clone();
+    wasm_fn.sig.abi = None;
+    for input in &mut wasm_fn.sig.inputs {
+        if let syn::FnArg::Typed(arg) = input {
+            arg.attrs.retain(|attr| !attr.path().is_ident("reprfn"));
+        }
+    }
+    let wasm_cfg: syn::Meta = match &args.cfg {
+        Some(cfg) => syn::parse_quote! { all(#cfg, target_arch = "wasm32") },
+        None => syn::parse_quote! { target_arch = "wasm32" },
+    };
+    let feature_quote = feature_quote(args.feature.as_ref(), Some(&wasm_cfg), args.pointer_width.as_ref());
+    let mut expanded = quote::quote! {
+        #feature_quote
+        #[::wasm_bindgen::prelude::wasm_bindgen]
+        #wasm_fn
+    };
+
+    let mut native_args = args;
+    native_args.cfg = Some(match &native_args.cfg {
+        Some(cfg) => syn::parse_quote! { all(#cfg, not(target_arch = "wasm32")) },
+        None => syn::parse_quote! { not(target_arch = "wasm32") },
+    });
+    expanded.extend(expand_fn(native_args, input)?);
+    Ok(expanded)
+}
+
+/// One function per target ABI, calling a single copy of the body
+fn expand_abi_targets(args: Args, abi_targets: target::Targeted, input: syn::ItemFn) -> syn::Result<TokenStream2> {
+    let syn::ItemFn { attrs, vis, sig, block } = input;
+    if let Some(source_abi) = &sig.abi {
+        if !args.override_abi {
+            return Err(syn::Error::new_spanned(source_abi, "the function already has an ABI, per-target ABIs replace it only with `override_abi = true`"));
+        }
+    }
+    if let Some(param) = sig.generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "per-target ABIs cannot be used on generic functions"));
+    }
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new_spanned(variadic, "per-target ABIs cannot be used on variadic functions"));
+    }
+    if block.stmts.is_empty() || matches!(args.mode, Some(Mode::Import)) {
+        return Err(syn::Error::new_spanned(&sig.ident, "per-target ABIs can only be used on exported functions"));
+    }
+
+    // The shared body takes the parameters as declared, hints are applied by each export
+    let mut body_inputs = sig.inputs.clone();
+    for input in &mut body_inputs {
+        if let syn::FnArg::Typed(arg) = input {
+            arg.attrs.retain(|attr| !attr.path().is_ident("reprfn"));
+        }
+    }
+    let params::Forward { inputs: body_inputs, args: body_args } = params::forward(&body_inputs)?;
+    let body_ident = quote::format_ident!("__reprfn_{}_body", sig.ident);
+    let constness = &sig.constness;
+    let unsafety = &sig.unsafety;
+    let output = &sig.output;
+    let feature_quote = feature_quote(args.feature.as_ref(), args.cfg.as_ref(), args.pointer_width.as_ref());
+    let mut expanded = quote::quote! {
+        #feature_quote
+        #[inline(always)]
+        #[allow(dead_code)]
+        #constness #unsafety fn #body_ident(#body_inputs) #output #block
+    };
+    let call_quote = if unsafety.is_some() {
+        quote::quote! { unsafe { #body_ident(#(#body_args),*) } }
+    } else {
+        quote::quote! { #body_ident(#(#body_args),*) }
+    };
+
+    for (predicate, abi_value) in abi_targets.predicates() {
+        let mut target_args = args.clone();
+        target_args.abi = Abi::Explicit(abi_value.clone());
+        target_args.cfg = Some(match &args.cfg {
+            Some(cfg) => syn::parse_quote! { all(#cfg, #predicate) },
+            None => syn::parse_quote! { #predicate },
+        });
+        let mut target_sig = sig.clone();
+        target_sig.abi = None;
+        let target_block = syn::parse_quote! {{ #call_quote }};
+        expanded.extend(expand_fn(target_args, syn::ItemFn { attrs: attrs.clone(), vis: vis.clone(), sig: target_sig, block: target_block })?);
+    }
+    Ok(expanded)
+}
+
+/// The export, or only its declaration when the crate is built with `--cfg reprfn_import`
+fn expand_import_cfg(args: Args, input: syn::ItemFn) -> syn::Result<TokenStream2> {
+    let mut expanded = TokenStream2::new();
+    for (mode, predicate) in [(Mode::Export, quote::quote! { not(reprfn_import) }), (Mode::Import, quote::quote! { reprfn_import })] {
+        let mut mode_args = args.clone();
+        mode_args.mode = Some(mode.clone());
+        mode_args.cfg = Some(match &args.cfg {
+            Some(cfg) => syn::parse_quote! { all(#cfg, #predicate) },
+            None => syn::parse_quote! { #predicate },
+        });
+        match (mode, expand_fn(mode_args, input.clone())) {
+            (_, Ok(mode_expanded)) => expanded.extend(mode_expanded),
+            // Attributes only exports have are fine until the crate is actually built as the consumer
+            (Mode::Import, Err(err)) => {
+                let mut error = syn::Error::new_spanned(&input.sig.ident, "this export cannot become an import under `--cfg reprfn_import`, set `mode = \"export\"` to keep it an export");
+                error.combine(err);
+                let error = error.to_compile_error();
+                expanded.extend(quote::quote! {
+                    #[cfg(reprfn_import)]
+                    const _: () = { #error };
+                });
+            },
+            (Mode::Export, Err(err)) => return Err(err),
+        }
+    }
+    Ok(expanded)
+}
+
+/// The function being expanded, rewritten in place as the options apply
+struct Func {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    sig: syn::Signature,
+    block: Box<syn::Block>,
+    mode: Mode,
+    /// Gates the export, or the declaration of an import
+    feature_quote: TokenStream2,
+    /// Gates the Rust functions wrapping an import
+    wrapper_feature_quote: TokenStream2,
+    /// Gates the fallback of a swappable import, where its declaration is gated out
+    fallback_feature_quote: Option<TokenStream2>,
+    /// Items generated alongside the function
+    aux: TokenStream2,
+}
+
+/// The attributes and ABIs of the generated items, once every option is checked
+struct Quotes {
+    abi: TokenStream2,
+    then: TokenStream2,
+    cold: TokenStream2,
+    ffi: TokenStream2,
+    link: TokenStream2,
+    require_link: TokenStream2,
+    unsafe_block: TokenStream2,
+    safe: TokenStream2,
+}
+
+/// The `#[cfg]` of the export or declaration, of the wrappers of an import and of the fallback of a swappable one
+fn feature_quotes(args: &Args) -> syn::Result<(TokenStream2, TokenStream2, Option<TokenStream2>)> {
+    // Gating the wrapper alone keeps the declaration it calls, gating the declaration alone needs something to call
+    // instead, which only swappable imports have
+    let wrapped = args.is_wrapped();
+    let gated_quote = feature_quote(args.feature.as_ref(), args.cfg.as_ref(), args.pointer_width.as_ref());
+    let ungated_quote = feature_quote(None, args.cfg.as_ref(), args.pointer_width.as_ref());
+    // Where the declaration of a swappable import is gated out, a fallback takes its place
+    let fallback_feature_quote = args.feature.as_ref().map(|feature| {
+        let raw = feature.value();
+        let negated = match raw.strip_prefix('!') {
+            Some(raw) => raw.to_string(),
+            None => format!("!{}", raw),
+        };
+        feature_quote(Some(&syn::LitStr::new(&negated, feature.span())), args.cfg.as_ref(), args.pointer_width.as_ref())
+    });
+    let (feature_quote, wrapper_feature_quote) = match &args.feature_target {
+        FeatureTarget::Wrapper(applies_to) if !wrapped => {
+            return Err(syn::Error::new(applies_to.span(), "only imports with `import_style = \"item\"` or `swappable` have a wrapper"));
+        },
+        FeatureTarget::Wrapper(_) => (ungated_quote, gated_quote),
+        FeatureTarget::Export(applies_to) if wrapped && args.swappable.is_none() => {
+            return Err(syn::Error::new(applies_to.span(), "the wrapper calls the declaration, use `applies_to = \"wrapper\"` instead"));
+        },
+        FeatureTarget::Export(_) => (gated_quote, ungated_quote),
+        FeatureTarget::All => (gated_quote.clone(), gated_quote),
+    };
+    Ok((feature_quote, wrapper_feature_quote, fallback_feature_quote))
+}
+
+/// `#[no_sanitize]`, applied to the export
+fn no_sanitize(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let Some(sanitizer) = args.no_sanitize.first() else {
+        return Ok(());
+    };
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(sanitizer.span(), "`no_sanitize` can only be used on exported functions"));
+    }
+    let sanitizers = args.no_sanitize.iter().map(|sanitizer| syn::Ident::new(&sanitizer.value(), sanitizer.span()));
+    func.attrs.push(syn::parse_quote! { #[no_sanitize(#(#sanitizers),*)] });
+    Ok(())
+}
+
+/// `ffi_const` is the stricter `ffi_pure`, the function does not even read memory
+fn ffi_quote(args: &Args, func: &Func) -> syn::Result<TokenStream2> {
+    Ok(match (&args.ffi_pure, &args.ffi_const) {
+        (Some(_), Some(ffi_const)) => {
+            return Err(syn::Error::new(ffi_const.span(), "`ffi_pure` and `ffi_const` are mutually exclusive"));
+        },
+        (Some(hint), None) | (None, Some(hint)) if matches!(func.mode, Mode::Export) => {
+            let option = if args.ffi_pure.is_some() { "ffi_pure" } else { "ffi_const" };
+            return Err(syn::Error::new(hint.span(), format!("`{}` can only be used on imported functions", option)));
+        },
+        (Some(_), None) => quote::quote! { #[unsafe(ffi_pure)] },
+        (None, Some(_)) => quote::quote! { #[unsafe(ffi_const)] },
+        (None, None) => quote::quote! {},
+    })
+}
+
+/// `#[cold]` and `#[inline]`, which cannot both be asked for
+fn inline_quotes(args: &Args, func: &Func) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let cold_quote = if args.cold {
+        if let Some((Inline::Always, inline_value)) = &args.inline {
+            return Err(syn::Error::new(inline_value.span(), "`cold` and `inline = \"always\"` contradict each other"));
+        }
+        quote::quote! {
+            #[cold]
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    let inline_quote = match &args.inline {
+        Some((_, inline_value)) if matches!(func.mode, Mode::Import) => {
+            return Err(syn::Error::new(inline_value.span(), "`inline` can only be used on exported functions"));
+        },
+        Some((Inline::Always, _)) => quote::quote! { #[inline(always)] },
+        Some((Inline::Never, _)) => quote::quote! { #[inline(never)] },
+        Some((Inline::Hint, _)) => quote::quote! { #[inline] },
+        None => quote::quote! {},
+    };
+    Ok((cold_quote, inline_quote))
+}
+
+/// Imports go in `unsafe extern` blocks, as edition 2024 requires, and are unsafe to call unless declared `safe`
+fn safety_quotes(args: &Args, func: &Func) -> syn::Result<(TokenStream2, TokenStream2)> {
+    Ok(match (&func.mode, args.safe, &func.sig.unsafety) {
+        (Mode::Import, true, Some(unsafety)) => {
+            return Err(syn::Error::new_spanned(unsafety, "an import cannot be both `safe` and `unsafe`, remove one of them"));
+        },
+        (Mode::Import, true, None) => (quote::quote! { unsafe }, quote::quote! { safe }),
+        (Mode::Import, false, unsafety) => (quote::quote! { unsafe }, quote::quote! { #unsafety }),
+        (Mode::Export, true, _) => {
+            return Err(syn::Error::new_spanned(&func.sig.ident, "`safe` can only be used on imported functions"));
+        },
+        (Mode::Export, false, _) => (quote::quote! {}, quote::quote! {}),
+    })
+}
+
+fn expand_fn(mut args: Args, input: syn::ItemFn) -> syn::Result<TokenStream2> {
+    if let Some(mode_features) = args.mode_features.take() {
+        return expand_mode_features(args, mode_features, input);
+    }
+    if let Some(wasm_bindgen) = args.wasm_bindgen.take() {
+        return expand_wasm_bindgen(args, wasm_bindgen, input);
+    }
+    if let Some(abi_targets) = args.abi_targets.take() {
+        return expand_abi_targets(args, abi_targets, input);
+    }
+    if args.mode.is_none() && !args.panic_hook && !input.block.stmts.is_empty() && defaults::get().import_cfg {
+        return expand_import_cfg(args, input);
+    }
+
+    let syn::ItemFn { mut attrs, vis, mut sig, block } = input;
+    // Everything but the module itself and the re-export moves into `module`
+    let (vis, module_vis) = match &args.module {
+        Some(_) => (nested_vis(&vis), vis),
+        None => (vis.clone(), vis),
+    };
+    let source_abi = sig.abi.take();
+
+    if let Some(unstable_attr) = &args.unstable_attr {
+        attrs.push(syn::parse_quote! { #[#unstable_attr] });
+    }
+    header::doc_attrs(&args, &mut attrs);
+
+    traits::resolve(&mut args, source_abi, &sig.ident)?;
+    convention::resolve(&mut args, &attrs, &sig.ident)?;
+    let (feature_quote, wrapper_feature_quote, fallback_feature_quote) = feature_quotes(&args)?;
+
+    // Determine mode if not provided, based on the presence of a block or a semicolon
+    let mode = match &args.mode {
+        Some(mode) => mode.clone(),
+        // The body of the setter is generated
+        None if args.panic_hook => Mode::Export,
+        None if block.stmts.is_empty() => Mode::Import,
+        None => Mode::Export,
+    };
+    let mut func = Func { attrs, vis, sig, block, mode, feature_quote, wrapper_feature_quote, fallback_feature_quote, aux: TokenStream2::new() };
+
+    convention::check_mode(&args, &func)?;
+    body::check_const(&args, &func)?;
+    body::check_naked(&args, &func)?;
+    no_sanitize(&args, &mut func)?;
+    let ffi_quote = ffi_quote(&args, &func)?;
+    params::check_count(&args, &func)?;
+    traits::uefi(&mut args, &func)?;
+    if args.panic_hook {
+        hook::expand(&mut func)?;
+    }
+    body::forward(&mut args, &mut func)?;
+    params::apply(&args, &mut func)?;
+    types::apply_deep_abi(&args, &mut func)?;
+    traits::check_signature(&args, &mut func)?;
+
+    let (abi_quote, export_abi_quote) = traits::quote(&args, &func)?;
+    let (name_quote, no_mangle_quote) = convention::quote(&args, &func)?;
+    let (cold_quote, inline_quote) = inline_quotes(&args, &func)?;
+    let then_quote = match &args.then {
+        Some(then_path) => quote::quote! {
+            #[#then_path]
+        },
+        None => quote::quote! {},
+    };
+    traits::check_import(&args, &func)?;
+    let (link_quote, require_link_quote) = link::import_quotes(&args, &mut func, &abi_quote)?;
+    let (unsafe_block_quote, safe_quote) = safety_quotes(&args, &func)?;
+
+    body::transform(&args, &mut func)?;
+    link::loader_aux(&args, &mut func)?;
+    mono::expand(&args, &mut func)?;
+    convention::symbol_const(&args, &mut func)?;
+    section::register(&args, &mut func, &export_abi_quote)?;
+    link::rust_wrapper(&args, &mut func)?;
+    layout::expand(&args, &mut func)?;
+    types::check_repr_c(&args, &mut func)?;
+    types::suggest_ffi_types(&args, &mut func);
+    header::emit(&args, &mut func)?;
+    let naked_quote = body::naked(&args);
+
+    let ident = &func.sig.ident;
+    // Spelled from the scope the function is expanded in, through the module when it moved there
+    let reexport_quote = if let Some(rename) = &args.reexport_as {
+        if args.anon.is_some() {
+            return Err(syn::Error::new_spanned(rename, "`anon` functions have no path to re-export"));
+        }
+        let feature_quote = &func.feature_quote;
+        match &args.module {
+            Some(module) => quote::quote! {
+                #feature_quote
+                #module_vis use self::#module::#ident as #rename;
+            },
+            None if rename == ident => {
+                return Err(syn::Error::new_spanned(rename, format!("'{}' already is the name of the function", rename)));
+            },
+            None => quote::quote! {
+                #feature_quote
+                #module_vis use self::#ident as #rename;
+            },
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    let quotes = Quotes {
+        abi: abi_quote,
+        then: then_quote,
+        cold: cold_quote,
+        ffi: ffi_quote,
+        link: link_quote,
+        require_link: require_link_quote,
+        unsafe_block: unsafe_block_quote,
+        safe: safe_quote,
+    };
+    // Imports wrapped in a function item replace the declaration
+    let wrapped = match link::item_style(&args, &func, &quotes)? {
+        Some(expanded) => Some(expanded),
+        None => link::swappable(&args, &func, &quotes)?,
+    };
+    let expanded = match wrapped {
+        Some(expanded) => expanded,
+        None => {
+            let Func { attrs, vis, sig, block, mode, feature_quote, aux, .. } = &func;
+            let syn::Signature { constness, unsafety, fn_token, paren_token, inputs, variadic, output, generics, .. } = sig;
+            let syn::Generics { lt_token, params, gt_token, where_clause } = generics;
+            let Quotes { abi: abi_quote, then: then_quote, cold: cold_quote, ffi: ffi_quote, link: link_quote, require_link: require_link_quote, unsafe_block: unsafe_block_quote, safe: safe_quote } = &quotes;
+
+            let mut args_quote = TokenStream2::new();
+            paren_token.surround(&mut args_quote, |tokens| {
+                inputs.to_tokens(tokens);
+                variadic.to_tokens(tokens);
+            });
+
+            match mode {
+                Mode::Export => quote::quote! {
+                    #then_quote
+                    #(#attrs)*
+                    #feature_quote
+                    #name_quote
+                    #cold_quote
+                    #inline_quote
+                    #naked_quote
+                    #no_mangle_quote
+                    #vis #constness #unsafety #export_abi_quote #fn_token #ident #lt_token #params #gt_token #args_quote #output #where_clause #block
+                    #aux
+                },
+                Mode::Import => quote::quote! {
+                    #link_quote
+                    #unsafe_block_quote #abi_quote {
+                        #then_quote
+                        #(#attrs)*
+                        #feature_quote
+                        #name_quote
+                        #cold_quote
+                        #inline_quote
+                        #ffi_quote
+                        #vis #safe_quote #fn_token #ident #lt_token #params #gt_token #args_quote #output #where_clause;
+                    }
+                    #require_link_quote
+                },
+            }
+        },
+    };
+
+    // Symbol names ignore module paths, so only the Rust side moves into the module
+    let expanded = if let Some(module) = &args.module {
+        quote::quote! {
+            #module_vis mod #module {
+                use super::*;
+
+                #expanded
+            }
+        }
+    } else {
+        expanded
+    };
+    let expanded = quote::quote! {
+        #expanded
+        #reexport_quote
+    };
+
+    // The symbol is still defined from inside the anonymous const, only the Rust item is out of reach
+    if args.anon.is_some() {
+        return Ok(quote::quote! {
+            const _: () = {
+                #expanded
+            };
+        });
+    }
+
+    Ok(expanded)
+}
+
+/// The trait stays as is, its default methods are exported as free functions
+fn expand_trait(args: Args, input: syn::ItemTrait) -> syn::Result<TokenStream2> {
+    let mut expanded = input.to_token_stream();
+    for item in &input.items {
+        let syn::TraitItem::Fn(syn::TraitItemFn { attrs, sig, default: Some(block), .. }) = item else {
+            continue;
+        };
+        if let Some(receiver) = sig.receiver() {
+            return Err(syn::Error::new_spanned(receiver, "default methods taking `self` cannot be exported, there is no value to call them on"));
+        }
+        let mut self_spans = Vec::new();
+        syn::visit::visit_signature(&mut SelfFinder(&mut self_spans), sig);
+        syn::visit::visit_block(&mut SelfFinder(&mut self_spans), block);
+        if let Some(span) = self_spans.first() {
+            return Err(syn::Error::new(*span, "exported default methods cannot refer to `Self`"));
+        }
+        let block = Box::new(block.clone());
+        expanded.extend(expand_fn(args.clone(), syn::ItemFn { attrs: attrs.clone(), vis: input.vis.clone(), sig: sig.clone(), block })?);
+    }
+    Ok(expanded)
+}
+
+/// Spans of `Self`, which has no meaning in a free function
+struct SelfFinder<'a>(&'a mut Vec<proc_macro2::Span>);
+
+impl syn::visit::Visit<'_> for SelfFinder<'_> {
+    fn visit_ident(&mut self, ident: &syn::Ident) {
+        if ident == "Self" {
+            self.0.push(ident.span());
+        }
+    }
+}
+
+fn expand_foreign_mod(args: Args, input: syn::ItemForeignMod) -> syn::Result<TokenStream2> {
+    let Args { abi, name, name_targets, feature, cfg, pointer_width, link, mode, safe, .. } = args;
+    let syn::ItemForeignMod { mut attrs, unsafety, abi: mut block_abi, brace_token: _, mut items } = input;
+
+    match abi {
+        Abi::Explicit(abi_value) => block_abi.name = Some(abi_value),
+        Abi::None => {
+            return Err(syn::Error::new_spanned(block_abi, "extern blocks always have an ABI, `abi = \"none\"` cannot be used here"));
+        },
+        Abi::Unspecified => {},
+    }
+
+    if let Some(Mode::Export) = mode {
+        return Err(syn::Error::new_spanned(block_abi, "extern blocks can only import, `mode = \"export\"` cannot be used here"));
+    }
+
+    if name_targets.is_some() {
+        return Err(syn::Error::new_spanned(block_abi, "per-target names cannot be used on extern blocks"));
+    }
+
+    // The block is emitted `unsafe extern` either way, the items keep their own `safe` or `unsafe`
+    let unsafety = unsafety.unwrap_or_default();
+
+    if let Some(link_value) = link {
+        let link_quote = link_value.quote();
+        attrs.push(syn::parse_quote!(#link_quote));
+    }
+
+    // Every item of the block shares the feature gate and the name prefix
+    for item in &mut items {
+        let (item_attrs, ident) = match item {
+            syn::ForeignItem::Fn(item) => (&mut item.attrs, &item.sig.ident),
+            syn::ForeignItem::Static(item) => (&mut item.attrs, &item.ident),
+            _ => continue,
+        };
+
+        let feature_quote = feature_quote(feature.as_ref(), cfg.as_ref(), pointer_width.as_ref());
+        item_attrs.extend(syn::parse::Parser::parse2(syn::Attribute::parse_outer, feature_quote)?);
+
+        if let Some(prefix) = &name {
+            if find_attr(item_attrs, "link_name").is_none() {
+                let link_name = syn::LitStr::new(&format!("{}{}", prefix.value(), ident.unraw()), prefix.span());
+                item_attrs.push(syn::parse_quote! {
+                    #[link_name = #link_name]
+                });
+            }
+        }
+    }
+
+    if safe {
+        for item in &mut items {
+            let safe_item = match item {
+                syn::ForeignItem::Fn(syn::ForeignItemFn { sig, .. }) if sig.unsafety.is_some() => {
+                    return Err(syn::Error::new_spanned(sig.unsafety, "an import cannot be both `safe` and `unsafe`, remove one of them"));
+                },
+                syn::ForeignItem::Fn(syn::ForeignItemFn { attrs, vis, sig, .. }) => quote::quote! {
+                    #(#attrs)*
+                    #vis safe #sig;
+                },
+                syn::ForeignItem::Static(syn::ForeignItemStatic { attrs, vis, static_token, mutability, ident, ty, .. }) => quote::quote! {
+                    #(#attrs)*
+                    #vis safe #static_token #mutability #ident: #ty;
+                },
+                _ => continue,
+            };
+            *item = syn::ForeignItem::Verbatim(safe_item);
+        }
+    }
+
+    let expanded = quote::quote! {
+        #(#attrs)*
+        #unsafety #block_abi {
+            #(#items)*
+        }
+    };
+
+    Ok(expanded)
+}
+
+fn expand_static(args: Args, input: syn::ItemStatic) -> syn::Result<TokenStream2> {
+    let Args { abi, name, name_targets, feature, cfg, pointer_width, link, mode, accessor, .. } = args;
+    let syn::ItemStatic { attrs, vis, static_token, mutability, ident, ty, expr, .. } = input;
+
+    if let (Some(attr), true) = (find_attr(&attrs, "export_name"), name.is_some() || name_targets.is_some()) {
+        return Err(syn::Error::new_spanned(attr, "Duplicate `#[export_name]`, remove it and only use the `name` attribute"));
+    }
+
+    let feature_quote = feature_quote(feature.as_ref(), cfg.as_ref(), pointer_width.as_ref());
+
+    // A static with a value is exported unless asked otherwise
+    if let Some(Mode::Import) = mode {
+        if let Some(accessor) = accessor {
+            return Err(syn::Error::new_spanned(accessor, "`accessor` can only be used on exported statics"));
+        }
+        if name_targets.is_some() {
+            return Err(syn::Error::new_spanned(ident, "per-target names can only be used on exported statics"));
+        }
+
+        let link_quote = if let Some(link_value) = link {
+            link_value.quote()
+        } else {
+            quote::quote! {}
+        };
+
+        let name_quote = if let Some(name_value) = name {
+            quote::quote_spanned! { name_value.span()=>
+                #[link_name = #name_value]
+            }
+        } else {
+            quote::quote! {}
+        };
+
+        let abi_quote = match abi {
+            Abi::Explicit(abi_value) => quote::quote! {
+                extern #abi_value
+            },
+            Abi::Unspecified => {
+                let message = format!("Missing ABI for imported static '{}', use `abi = \"Rust\"` to import through the Rust ABI", ident);
+                return Err(syn::Error::new(ident.span(), message));
+            },
+            Abi::None => {
+                return Err(syn::Error::new(ident.span(), "Imported statics always have an ABI, `abi = \"none\"` cannot be used here"));
+            },
+        };
+
+        return Ok(quote::quote! {
+            #link_quote
+            unsafe #abi_quote {
+                #(#attrs)*
+                #feature_quote
+                #name_quote
+                #vis #static_token #mutability #ident: #ty;
+            }
+        });
+    }
+
+    if let Some(link_value) = link {
+        return Err(syn::Error::new(link_value.span(), "`link` can only be used on imported statics"));
+    }
+
+    let name_quote = if let Some(name_value) = name {
+        quote::quote_spanned! { name_value.span()=>
+            #[export_name = #name_value]
+        }
+    } else if let Some(name_targets) = name_targets {
+        let name_quote = name_targets.cfg_attr_quote(|name_value| quote::quote! { export_name = #name_value });
+        if find_attr(&attrs, "no_mangle").is_none() {
+            quote::quote! {
+                #name_quote
+                #[no_mangle]
+            }
+        } else {
+            name_quote
+        }
+    } else if find_attr(&attrs, "no_mangle").is_none() {
+        quote::quote! {
+            #[no_mangle]
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    // The accessor hands out the address of the static through the requested ABI, C by default
+    let accessor_quote = if let Some(accessor) = accessor {
+        let abi_quote = match abi {
+            Abi::Explicit(abi_value) => quote::quote! {
+                extern #abi_value
+            },
+            Abi::Unspecified => quote::quote! {
+                extern "C"
+            },
+            Abi::None => quote::quote! {},
+        };
+        let pointer_quote = match mutability {
+            syn::StaticMutability::Mut(_) => quote::quote! {
+                *mut #ty { ::core::ptr::addr_of_mut!(#ident) }
+            },
+            _ => quote::quote! {
+                *const #ty { ::core::ptr::addr_of!(#ident) }
+            },
+        };
+        quote::quote! {
+            #feature_quote
+            #[no_mangle]
+            #vis #abi_quote fn #accessor() -> #pointer_quote
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    Ok(quote::quote! {
+        #(#attrs)*
+        #feature_quote
+        #name_quote
+        #vis #static_token #mutability #ident: #ty = #expr;
+        #accessor_quote
+    })
+}
+
+/// The expansion of `#[reprfn(...)]` on a function, static, extern block or trait, built from another proc macro
+///
+/// Options without a method of their own are passed as they would be written in the attribute.
+///
+/// ```
+/// let item: syn::ItemFn = syn::parse_quote! {
+///     pub fn add(a: i32, b: i32) -> i32 {
+///         a + b
+///     }
+/// };
+/// let expanded = reprfn_core::Builder::new(item)
+///     .abi("C")
+///     .name("my_add")
+///     .export()
+///     .arg(quote::quote! { cold = true })
+///     .build()
+///     .unwrap();
+/// assert!(expanded.to_string().contains("export_name = \"my_add\""));
+/// ```
+pub struct Builder {
+    item: syn::Item,
+    args: Vec<TokenStream2>,
+}
+
+impl Builder {
+    pub fn new(item: impl Into<syn::Item>) -> Self {
+        Builder { item: item.into(), args: Vec::new() }
+    }
+
+    pub fn abi(self, abi: &str) -> Self {
+        let abi = syn::LitStr::new(abi, proc_macro2::Span::call_site());
+        self.arg(quote::quote! { abi = #abi })
+    }
+
+    pub fn name(self, name: &str) -> Self {
+        let name = syn::LitStr::new(name, proc_macro2::Span::call_site());
+        self.arg(quote::quote! { name = #name })
+    }
+
+    pub fn export(self) -> Self {
+        self.arg(quote::quote! { mode = "export" })
+    }
+
+    pub fn import(self) -> Self {
+        self.arg(quote::quote! { mode = "import" })
+    }
+
+    /// Any other option, `quote! { cold = true }`, later options replacing earlier ones like in the attribute
+    pub fn arg(mut self, arg: TokenStream2) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    /// Runs the same validation as the attribute, returning its errors instead of `compile_error!` invocations
+    pub fn build(self) -> syn::Result<TokenStream2> {
+        let Builder { item, args: arg_tokens } = self;
+        expand_with(parse_args(quote::quote! { #(#arg_tokens),* })?, item)
+    }
+}
@@ -0,0 +1,184 @@
+//! Monomorphized `extern "C"` trampolines for generic functions
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::visit_mut::VisitMut;
+
+use super::{params, Args, Func, Mode};
+
+/// The signature shared by every instance of a generic function
+struct Generic<'a> {
+    vis: &'a syn::Visibility,
+    ident: &'a syn::Ident,
+    unsafety: Option<syn::Token![unsafe]>,
+    params: &'a Punctuated<syn::GenericParam, syn::Token![,]>,
+    inputs: &'a Punctuated<syn::FnArg, syn::Token![,]>,
+    output: &'a syn::ReturnType,
+    /// The `#[cfg]` gating the function, repeated on each trampoline
+    feature_quote: &'a TokenStream2,
+}
+
+struct Substitute<'a> {
+    map: &'a [(syn::Ident, syn::Type)],
+}
+
+impl VisitMut for Substitute<'_> {
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        if let syn::Type::Path(path) = ty {
+            if path.qself.is_none() {
+                if let Some(ident) = path.path.get_ident() {
+                    if let Some((_, concrete)) = self.map.iter().find(|(param, _)| param == ident) {
+                        *ty = concrete.clone();
+                        return;
+                    }
+                }
+            }
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+/// `ident_u8`, `ident_u8_f32` for `(u8, f32)`, ...
+fn instance_ident(ident: &syn::Ident, types: &[syn::Type]) -> syn::Ident {
+    let mut name = ident.unraw().to_string();
+    for ty in types {
+        let ty = quote::quote!(#ty).to_string();
+        let ty = ty.split(|c: char| !c.is_ascii_alphanumeric()).filter(|part| !part.is_empty()).collect::<Vec<_>>();
+        name.push('_');
+        name.push_str(&ty.join("_"));
+    }
+    syn::Ident::new(&name, ident.span())
+}
+
+/// Generic functions cannot cross the C ABI, their instances can
+pub(crate) fn expand(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let Some(instances) = &args.instances else {
+        return Ok(());
+    };
+    let ident = &func.sig.ident;
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new_spanned(ident, "`instances` can only be used on exported functions"));
+    }
+    if !args.abi.is_rust() {
+        return Err(syn::Error::new_spanned(ident, "`instances` needs the generic function to use the Rust ABI"));
+    }
+    let generic = Generic {
+        vis: &func.vis,
+        ident,
+        unsafety: func.sig.unsafety,
+        params: &func.sig.generics.params,
+        inputs: &func.sig.inputs,
+        output: &func.sig.output,
+        feature_quote: &func.feature_quote,
+    };
+    let instances = instances.iter().map(|instance| instance.parse()).collect::<syn::Result<Vec<syn::Type>>>()?;
+    let trampolines = trampolines(&generic, &instances)?;
+    func.aux.extend(trampolines);
+    Ok(())
+}
+
+/// One trampoline per instance, functions with several type parameters take a tuple per instance
+fn trampolines(generic: &Generic, instances: &[syn::Type]) -> syn::Result<TokenStream2> {
+    let mut type_params = Vec::new();
+    let mut lifetimes = Vec::new();
+    for param in generic.params {
+        match param {
+            syn::GenericParam::Type(param) => type_params.push(param.ident.clone()),
+            syn::GenericParam::Lifetime(param) => lifetimes.push(param.lifetime.clone()),
+            syn::GenericParam::Const(param) => {
+                return Err(syn::Error::new_spanned(param, "const parameters cannot be instantiated"));
+            },
+        }
+    }
+    if type_params.is_empty() {
+        return Err(syn::Error::new_spanned(generic.ident, "instantiated functions need at least one type parameter"));
+    }
+
+    let vis = generic.vis;
+    let ident = generic.ident;
+    let unsafety = generic.unsafety;
+    let feature_quote = generic.feature_quote;
+    let mut expanded = TokenStream2::new();
+    for instance in instances {
+        let types = match instance.clone() {
+            syn::Type::Tuple(tuple) if type_params.len() > 1 => tuple.elems.into_iter().collect::<Vec<_>>(),
+            ty => vec![ty],
+        };
+        if types.len() != type_params.len() {
+            let message = format!("expected {} types for the parameters of '{}', found {}", type_params.len(), ident, types.len());
+            return Err(syn::Error::new_spanned(instance, message));
+        }
+
+        let map = type_params.iter().cloned().zip(types.iter().cloned()).collect::<Vec<_>>();
+        let mut inputs = generic.inputs.clone();
+        let mut output = generic.output.clone();
+        let mut substitute = Substitute { map: &map };
+        for input in &mut inputs {
+            substitute.visit_fn_arg_mut(input);
+        }
+        substitute.visit_return_type_mut(&mut output);
+
+        let params::Forward { inputs, args } = params::forward(&inputs)?;
+        let instance_ident = instance_ident(ident, &types);
+        expanded.extend(quote::quote! {
+            #feature_quote
+            #[no_mangle]
+            #vis #unsafety extern "C" fn #instance_ident<#(#lifetimes),*>(#inputs) #output {
+                #[allow(unused_unsafe)]
+                unsafe { #ident::<#(#types),*>(#(#args),*) }
+            }
+        });
+    }
+
+    Ok(expanded)
+}
+
+/// `reprfn_mono! { fn scale<T>(..) { .. } for [f32, f64] }`, the generic function and its instances
+pub(crate) struct Mono {
+    item: syn::ItemFn,
+    instances: Vec<syn::Type>,
+}
+
+impl syn::parse::Parse for Mono {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let item: syn::ItemFn = input.parse()?;
+        input.parse::<syn::Token![for]>()?;
+        let content;
+        syn::bracketed!(content in input);
+        let instances = content.parse_terminated(syn::Type::parse, syn::Token![,])?;
+        if instances.is_empty() {
+            return Err(syn::Error::new(content.span(), "expected at least one instance"));
+        }
+        Ok(Mono { item, instances: instances.into_iter().collect() })
+    }
+}
+
+impl Mono {
+    /// The generic function, left as is, followed by its trampolines
+    pub(crate) fn quote(&self) -> syn::Result<TokenStream2> {
+        let item = &self.item;
+        let sig = &item.sig;
+        if let Some(abi) = sig.abi.as_ref().filter(|abi| abi.name.as_ref().is_some_and(|name| name.value() != "Rust")) {
+            return Err(syn::Error::new_spanned(abi, "`reprfn_mono!` needs the generic function to use the Rust ABI"));
+        }
+
+        // Trampolines of a function that is compiled out would call nothing
+        let cfgs = item.attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+        let feature_quote = quote::quote! { #(#cfgs)* };
+        let generic = Generic {
+            vis: &item.vis,
+            ident: &sig.ident,
+            unsafety: sig.unsafety,
+            params: &sig.generics.params,
+            inputs: &sig.inputs,
+            output: &sig.output,
+            feature_quote: &feature_quote,
+        };
+        let trampolines = trampolines(&generic, &self.instances)?;
+        Ok(quote::quote! {
+            #item
+            #trampolines
+        })
+    }
+}
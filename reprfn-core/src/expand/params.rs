@@ -0,0 +1,306 @@
+//! Per-parameter `#[reprfn(...)]` hints, rewriting how a parameter crosses the ABI boundary
+
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+use super::{types, Args, Func, Mode};
+
+enum Hint {
+    /// `#[reprfn(as = "u8")]`: the parameter is passed as another type and converted back on entry
+    As(Box<syn::Type>),
+    /// `#[reprfn(callback)]`: the function pointer parameter takes the ABI of the function
+    Callback,
+    /// `#[reprfn(nullable)]`: `Option<&T>` is passed as a possibly null `*const T`
+    Nullable,
+    /// `#[reprfn(slice_abi)]`: `&[T]` is passed as a pointer and a length
+    SliceAbi,
+    /// `#[reprfn(repr_int = "u32")]`: an enum passed as its discriminant and converted back with `TryFrom`
+    ReprInt(Box<syn::Type>),
+    /// `#[reprfn(out)]`: the pointer is written to, debug builds check it is not null on entry
+    Out,
+}
+
+/// Exported parameters after applying their hints
+struct Rewrite {
+    inputs: Punctuated<syn::FnArg, syn::Token![,]>,
+    /// Statements converting the parameters back, run before the original body
+    prelude: Vec<syn::Stmt>,
+    /// Whether the prelude trusts the caller, reading through its pointers or its discriminants, which makes the
+    /// export `unsafe`
+    dereferences: bool,
+}
+
+fn take_hints(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Vec<Hint>> {
+    let (hint_attrs, rest): (Vec<_>, Vec<_>) = attrs.drain(..).partition(|attr| attr.path().is_ident("reprfn"));
+    *attrs = rest;
+
+    let mut hints = Vec::new();
+    for attr in hint_attrs {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("as") {
+                let value: syn::LitStr = meta.value()?.parse()?;
+                hints.push(Hint::As(Box::new(value.parse()?)));
+            } else if meta.path.is_ident("callback") {
+                hints.push(Hint::Callback);
+            } else if meta.path.is_ident("nullable") {
+                hints.push(Hint::Nullable);
+            } else if meta.path.is_ident("slice_abi") {
+                hints.push(Hint::SliceAbi);
+            } else if meta.path.is_ident("repr_int") {
+                let value: syn::LitStr = meta.value()?.parse()?;
+                let ty: syn::Type = value.parse()?;
+                let is_int = matches!(&ty, syn::Type::Path(path) if path.qself.is_none() && path.path.get_ident().is_some_and(|ident| {
+                    ["u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize"].iter().any(|int| ident == int)
+                }));
+                if !is_int {
+                    return Err(syn::Error::new(value.span(), format!("invalid repr_int '{}', expecting an integer type", value.value())));
+                }
+                hints.push(Hint::ReprInt(Box::new(ty)));
+            } else if meta.path.is_ident("out") {
+                hints.push(Hint::Out);
+            } else {
+                return Err(meta.error("unknown parameter hint"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(hints)
+}
+
+fn binding(arg: &syn::PatType) -> syn::Result<&syn::Ident> {
+    match &*arg.pat {
+        syn::Pat::Ident(pat) => Ok(&pat.ident),
+        pat => Err(syn::Error::new_spanned(pat, "parameters with hints must be bound to a plain identifier")),
+    }
+}
+
+fn is_bool(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident("bool"))
+}
+
+/// The function pointer of a callback, possibly nullable through `Option`
+fn callback_fn(ty: &mut syn::Type) -> Option<&mut syn::TypeBareFn> {
+    match ty {
+        syn::Type::BareFn(bare_fn) => Some(bare_fn),
+        syn::Type::Paren(ty) => callback_fn(&mut ty.elem),
+        syn::Type::Path(ty) if ty.qself.is_none() => {
+            let segment = ty.path.segments.last_mut().filter(|segment| segment.ident == "Option")?;
+            match &mut segment.arguments {
+                syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first_mut() {
+                    Some(syn::GenericArgument::Type(ty)) => callback_fn(ty),
+                    _ => None,
+                },
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+/// The reference inside `Option<&T>` or `Option<&mut T>`
+fn optional_ref(ty: &syn::Type) -> Option<&syn::TypeReference> {
+    match ty {
+        syn::Type::Paren(ty) => optional_ref(&ty.elem),
+        syn::Type::Path(ty) if ty.qself.is_none() => {
+            let segment = ty.path.segments.last().filter(|segment| segment.ident == "Option")?;
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+                    Some(syn::GenericArgument::Type(syn::Type::Reference(reference))) => Some(reference),
+                    _ => None,
+                },
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+/// `abi` is the ABI of the function, applied to its callbacks
+fn rewrite(inputs: Punctuated<syn::FnArg, syn::Token![,]>, abi: Option<&syn::LitStr>) -> syn::Result<Rewrite> {
+    let mut rewrite = Rewrite { inputs: Punctuated::new(), prelude: Vec::new(), dereferences: false };
+
+    for pair in inputs.into_pairs() {
+        let (mut input, comma) = pair.into_tuple();
+        // A parameter passed as two, following it in the exported signature
+        let mut extra = None;
+        if let syn::FnArg::Typed(arg) = &mut input {
+            for hint in take_hints(&mut arg.attrs)? {
+                match hint {
+                    Hint::As(ty) => {
+                        let ident = binding(arg)?.clone();
+                        let original = std::mem::replace(&mut arg.ty, ty);
+                        // `bool` has no `as` conversion from integers
+                        rewrite.prelude.push(if is_bool(&original) {
+                            syn::parse_quote! {
+                                let #ident = #ident != 0;
+                            }
+                        } else {
+                            syn::parse_quote! {
+                                let #ident = #ident as #original;
+                            }
+                        });
+                    },
+                    Hint::Callback => {
+                        let span = arg.ty.span();
+                        let Some(bare_fn) = callback_fn(&mut arg.ty) else {
+                            return Err(syn::Error::new(span, "`callback` expects a function pointer type"));
+                        };
+                        // Without an explicit ABI the function is Rust, which plain `fn` pointers already are
+                        if let Some(abi) = abi {
+                            bare_fn.abi = Some(syn::parse_quote! { extern #abi });
+                        }
+                    },
+                    Hint::Nullable => {
+                        let ident = binding(arg)?.clone();
+                        let Some(reference) = optional_ref(&arg.ty) else {
+                            return Err(syn::Error::new_spanned(&arg.ty, "`nullable` expects an `Option<&T>` or `Option<&mut T>` type"));
+                        };
+                        let elem = &reference.elem;
+                        let (ty, stmt): (syn::Type, syn::Stmt) = if reference.mutability.is_some() {
+                            (syn::parse_quote! { *mut #elem }, syn::parse_quote! { let #ident = unsafe { #ident.as_mut() }; })
+                        } else {
+                            (syn::parse_quote! { *const #elem }, syn::parse_quote! { let #ident = unsafe { #ident.as_ref() }; })
+                        };
+                        *arg.ty = ty;
+                        rewrite.prelude.push(stmt);
+                        rewrite.dereferences = true;
+                    },
+                    Hint::SliceAbi => {
+                        let ident = binding(arg)?.clone();
+                        let syn::Type::Reference(reference) = &*arg.ty else {
+                            return Err(syn::Error::new_spanned(&arg.ty, "`slice_abi` expects a `&[T]` or `&mut [T]` type"));
+                        };
+                        let syn::Type::Slice(slice) = &*reference.elem else {
+                            return Err(syn::Error::new_spanned(&arg.ty, "`slice_abi` expects a `&[T]` or `&mut [T]` type"));
+                        };
+                        let elem = &slice.elem;
+                        types::check_ffi_safe(elem)?;
+
+                        let ptr = quote::format_ident!("{}_ptr", ident);
+                        let len = quote::format_ident!("{}_len", ident);
+                        // `from_raw_parts` needs a non-null pointer even for empty slices, which C often passes as null
+                        let (ty, stmt): (syn::Type, syn::Stmt) = if reference.mutability.is_some() {
+                            (syn::parse_quote! { *mut #elem }, syn::parse_quote! {
+                                let #ident: &mut [#elem] = if #len == 0 { &mut [] } else { unsafe { ::core::slice::from_raw_parts_mut(#ptr, #len) } };
+                            })
+                        } else {
+                            (syn::parse_quote! { *const #elem }, syn::parse_quote! {
+                                let #ident: &[#elem] = if #len == 0 { &[] } else { unsafe { ::core::slice::from_raw_parts(#ptr, #len) } };
+                            })
+                        };
+                        *arg.ty = ty;
+                        *arg.pat = syn::parse_quote! { #ptr };
+                        extra = Some(syn::parse_quote! { #len: usize });
+                        rewrite.prelude.push(stmt);
+                        rewrite.dereferences = true;
+                    },
+                    Hint::ReprInt(ty) => {
+                        let ident = binding(arg)?.clone();
+                        let original = std::mem::replace(&mut arg.ty, ty);
+                        let int = &arg.ty;
+                        let message = format!("`{}` is not a valid discriminant of `{}`", ident, quote::quote!(#original));
+                        // C cannot be checked at compile time, the caller vouches for a valid discriminant
+                        rewrite.prelude.push(syn::parse_quote! {
+                            let #ident: #original = match <#original as ::core::convert::TryFrom<#int>>::try_from(#ident) {
+                                Ok(value) => value,
+                                Err(_) => {
+                                    ::core::debug_assert!(false, #message);
+                                    unsafe { ::core::hint::unreachable_unchecked() }
+                                },
+                            };
+                        });
+                        rewrite.dereferences = true;
+                    },
+                    Hint::Out => {
+                        let ident = binding(arg)?.clone();
+                        if !matches!(&*arg.ty, syn::Type::Ptr(_)) {
+                            return Err(syn::Error::new_spanned(&arg.ty, "`out` expects a raw pointer type"));
+                        }
+                        let message = format!("out parameter `{}` is null", ident);
+                        rewrite.prelude.push(syn::parse_quote! {
+                            ::core::debug_assert!(!#ident.is_null(), #message);
+                        });
+                    },
+                }
+            }
+        }
+        rewrite.inputs.push_value(input);
+        if let Some(extra) = extra {
+            rewrite.inputs.push_punct(Default::default());
+            rewrite.inputs.push_value(extra);
+        }
+        if let Some(comma) = comma {
+            rewrite.inputs.push_punct(comma);
+        }
+    }
+
+    Ok(rewrite)
+}
+
+/// Parameter hints change the exported signature and convert back at the top of the body
+pub(crate) fn apply(args: &Args, func: &mut Func) -> syn::Result<()> {
+    if let Mode::Import = func.mode {
+        return reject(&func.sig.inputs, "parameter hints can only be used on exported functions");
+    }
+    let rewrite = rewrite(std::mem::take(&mut func.sig.inputs), args.abi.lit())?;
+    func.sig.inputs = rewrite.inputs;
+    func.block.stmts.splice(0..0, rewrite.prelude);
+    if rewrite.dereferences {
+        func.sig.unsafety.get_or_insert_with(Default::default);
+    }
+    Ok(())
+}
+
+/// Guards the parameter count of the ABI contract against drift
+pub(crate) fn check_count(args: &Args, func: &Func) -> syn::Result<()> {
+    if let Some(expected) = &args.assert_params {
+        let inputs = &func.sig.inputs;
+        if expected.base10_parse::<usize>()? != inputs.len() {
+            let message = format!("'{}' is expected to take {} parameters but takes {}", func.sig.ident, expected, inputs.len());
+            return Err(syn::Error::new(expected.span(), message));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects hints where there is no body to convert the parameters in, `message` saying why
+pub(crate) fn reject(inputs: &Punctuated<syn::FnArg, syn::Token![,]>, message: &str) -> syn::Result<()> {
+    for input in inputs {
+        if let syn::FnArg::Typed(arg) = input {
+            if let Some(attr) = arg.attrs.iter().find(|attr| attr.path().is_ident("reprfn")) {
+                return Err(syn::Error::new_spanned(attr, message));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a parameter is bound to `name`, for parameters added to a signature
+pub(crate) fn has_param(inputs: &Punctuated<syn::FnArg, syn::Token![,]>, name: &syn::Ident) -> bool {
+    inputs.iter().any(|input| matches!(input, syn::FnArg::Typed(arg) if matches!(&*arg.pat, syn::Pat::Ident(pat) if pat.ident == *name)))
+}
+
+/// Plain identifier parameters, for calling another function with the same arguments
+pub(crate) struct Forward {
+    pub(crate) inputs: Punctuated<syn::FnArg, syn::Token![,]>,
+    pub(crate) args: Vec<syn::Ident>,
+}
+
+pub(crate) fn forward(inputs: &Punctuated<syn::FnArg, syn::Token![,]>) -> syn::Result<Forward> {
+    let mut forward = Forward { inputs: Punctuated::new(), args: Vec::new() };
+
+    for input in inputs {
+        let arg = match input {
+            syn::FnArg::Typed(arg) => arg,
+            syn::FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(receiver, "functions taking `self` cannot be forwarded to"));
+            },
+        };
+        let ident = binding(arg)?;
+        let ty = &arg.ty;
+        forward.inputs.push(syn::parse_quote! { #ident: #ty });
+        forward.args.push(ident.clone());
+    }
+
+    Ok(forward)
+}
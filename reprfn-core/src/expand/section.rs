@@ -0,0 +1,134 @@
+//! Functions collected in a linker section, `register_in` places them and `section_slice!` reads them back
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::ext::IdentExt;
+
+use super::{Args, Func, Mode};
+
+/// Mach-O section names are at most 16 bytes, `__rf_` included
+const MAX_SECTION: usize = 11;
+
+pub(crate) fn validate(section: &syn::LitStr) -> syn::Result<()> {
+    let name = section.value();
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid || name.len() > MAX_SECTION {
+        let message = format!("invalid section '{}', expecting a C identifier of at most {} characters", name, MAX_SECTION);
+        return Err(syn::Error::new(section.span(), message));
+    }
+    Ok(())
+}
+
+/// The section of the entries on ELF, Mach-O and COFF targets, entries sort between the `$a` and `$c` COFF markers
+fn sections(name: &str) -> (String, String, String) {
+    (format!("reprfn_{}", name), format!("__DATA,__rf_{},regular,no_dead_strip", name), format!(".rf_{}$b", name))
+}
+
+/// A pointer to the function in the section, `#[used]` so nothing referencing it is not a reason to drop it
+fn entry(section: &syn::LitStr, ident: &syn::Ident, fn_type: &TokenStream2, feature_quote: &TokenStream2) -> TokenStream2 {
+    let (elf, macho, coff) = sections(&section.value());
+    let static_ident = quote::format_ident!("__REPRFN_REGISTER_{}", ident.unraw().to_string().to_uppercase());
+    quote::quote! {
+        #feature_quote
+        #[used]
+        #[cfg_attr(not(any(target_vendor = "apple", windows)), link_section = #elf)]
+        #[cfg_attr(target_vendor = "apple", link_section = #macho)]
+        #[cfg_attr(windows, link_section = #coff)]
+        static #static_ident: #fn_type = #ident;
+    }
+}
+
+/// `register_in`, the export typed as the pointer the section holds
+pub(crate) fn register(args: &Args, func: &mut Func, export_abi_quote: &TokenStream2) -> syn::Result<()> {
+    let Some(register_in) = &args.register_in else {
+        return Ok(());
+    };
+    let syn::Signature { ident, unsafety, generics, inputs, variadic, output, .. } = &func.sig;
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(register_in.span(), "`register_in` can only be used on exported functions"));
+    }
+    if let Some(param) = generics.params.first() {
+        return Err(syn::Error::new_spanned(param, "`register_in` cannot be used on generic functions"));
+    }
+    if let Some(variadic) = variadic {
+        return Err(syn::Error::new_spanned(variadic, "variadic functions cannot be registered"));
+    }
+    let arg_types = inputs.iter().filter_map(|input| match input {
+        syn::FnArg::Typed(arg) => Some(&arg.ty),
+        syn::FnArg::Receiver(_) => None,
+    });
+    let fn_type = quote::quote! { #unsafety #export_abi_quote fn(#(#arg_types),*) #output };
+    func.aux.extend(entry(register_in, ident, &fn_type, &func.feature_quote));
+    Ok(())
+}
+
+/// `section_slice!("COMMANDS", extern "C" fn())`
+pub(crate) struct Slice {
+    section: syn::LitStr,
+    ty: syn::Type,
+}
+
+impl syn::parse::Parse for Slice {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let section: syn::LitStr = input.parse()?;
+        validate(&section)?;
+        input.parse::<syn::Token![,]>()?;
+        let ty = input.parse()?;
+        input.parse::<Option<syn::Token![,]>>()?;
+        Ok(Slice { section, ty })
+    }
+}
+
+impl Slice {
+    /// The linker defines the bounds of ELF and Mach-O sections, COFF ones are bounded by empty markers around them
+    pub(crate) fn quote(&self) -> TokenStream2 {
+        let Slice { section, ty } = self;
+        let name = section.value();
+        let (elf, macho, _) = sections(&name);
+        let (elf_start, elf_stop) = (format!("__start_{}", elf), format!("__stop_{}", elf));
+        let macho_start = format!("\x01section$start$__DATA$__rf_{}", name);
+        let macho_stop = format!("\x01section$end$__DATA$__rf_{}", name);
+        let (coff_start, coff_stop) = (format!(".rf_{}$a", name), format!(".rf_{}$c", name));
+        quote::quote! {{
+            #[cfg(not(any(target_vendor = "apple", windows)))]
+            let (start, stop) = {
+                unsafe extern "Rust" {
+                    #[link_name = #elf_start]
+                    static START: [#ty; 0];
+                    #[link_name = #elf_stop]
+                    static STOP: [#ty; 0];
+                }
+                // An empty section is not emitted and its bounds would be undefined
+                #[used]
+                #[link_section = #elf]
+                static EMPTY: [#ty; 0] = [];
+                unsafe { (::core::ptr::addr_of!(START), ::core::ptr::addr_of!(STOP)) }
+            };
+            #[cfg(target_vendor = "apple")]
+            let (start, stop) = {
+                unsafe extern "Rust" {
+                    #[link_name = #macho_start]
+                    static START: [#ty; 0];
+                    #[link_name = #macho_stop]
+                    static STOP: [#ty; 0];
+                }
+                #[used]
+                #[link_section = #macho]
+                static EMPTY: [#ty; 0] = [];
+                unsafe { (::core::ptr::addr_of!(START), ::core::ptr::addr_of!(STOP)) }
+            };
+            #[cfg(windows)]
+            let (start, stop) = {
+                #[used]
+                #[link_section = #coff_start]
+                static START: [#ty; 0] = [];
+                #[used]
+                #[link_section = #coff_stop]
+                static STOP: [#ty; 0] = [];
+                (::core::ptr::addr_of!(START), ::core::ptr::addr_of!(STOP))
+            };
+            let len = (stop as usize - start as usize) / ::core::mem::size_of::<#ty>();
+            // Only `register_in` places entries in the section, each a `#ty` next to the others
+            unsafe { ::core::slice::from_raw_parts(start as *const #ty, len) }
+        }}
+    }
+}
@@ -0,0 +1,81 @@
+//! C status codes, written `{ Ok = 0, Err = 1 }` and generated as a `#[repr(C)]` enum
+
+use proc_macro2::TokenStream as TokenStream2;
+
+#[derive(Clone)]
+pub(crate) struct StatusEnum {
+    pub(crate) ident: syn::Ident,
+    variants: Vec<(syn::Ident, syn::LitInt)>,
+}
+
+impl StatusEnum {
+    /// The enum is named `Status` unless a name precedes the braces, `Errno { ... }`
+    pub(crate) fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let ident = if input.peek(syn::Ident) {
+            input.parse()?
+        } else {
+            syn::Ident::new("Status", input.span())
+        };
+        let content;
+        syn::braced!(content in input);
+
+        let mut status = StatusEnum { ident, variants: Vec::new() };
+        let mut discriminants = Vec::new();
+        while !content.is_empty() {
+            let variant: syn::Ident = content.parse()?;
+            content.parse::<syn::Token![=]>()?;
+            let negative = content.parse::<Option<syn::Token![-]>>()?;
+            let discriminant: syn::LitInt = content.parse()?;
+            let value = discriminant.base10_parse::<i64>()?;
+            let value = if negative.is_some() { -value } else { value };
+            let discriminant = syn::LitInt::new(&value.to_string(), discriminant.span());
+
+            if status.variants.iter().any(|(other, _)| *other == variant) {
+                return Err(syn::Error::new(variant.span(), format!("duplicate status '{}'", variant)));
+            }
+            if discriminants.contains(&value) {
+                return Err(syn::Error::new(discriminant.span(), format!("duplicate status code {}", value)));
+            }
+            discriminants.push(value);
+            status.variants.push((variant, discriminant));
+
+            if !content.is_empty() {
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+        if status.variants.is_empty() {
+            return Err(syn::Error::new(status.ident.span(), "status enums need at least one status"));
+        }
+        Ok(status)
+    }
+
+    /// The export returns the enum, a function without a return type gets it as one
+    pub(crate) fn apply_output(&self, output: &mut syn::ReturnType) -> syn::Result<()> {
+        let ident = &self.ident;
+        let syn::ReturnType::Type(_, ty) = output else {
+            *output = syn::parse_quote! { -> #ident };
+            return Ok(());
+        };
+        // A path to the enum, like `crate::Status`, is the enum too
+        let returns_enum = matches!(&**ty, syn::Type::Path(path) if path.qself.is_none() && path.path.segments.last().is_some_and(|segment| segment.ident == *ident && segment.arguments.is_none()));
+        if !returns_enum {
+            return Err(syn::Error::new_spanned(ty, format!("`status_enum` functions return the generated `{}`, leave the return type out or use `{}`", ident, ident)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn quote(&self, vis: &syn::Visibility, feature_quote: &TokenStream2) -> TokenStream2 {
+        let ident = &self.ident;
+        let variants = self.variants.iter().map(|(variant, _)| variant);
+        let discriminants = self.variants.iter().map(|(_, discriminant)| discriminant);
+        quote::quote! {
+            #feature_quote
+            #[repr(C)]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            #[allow(dead_code)]
+            #vis enum #ident {
+                #(#variants = #discriminants),*
+            }
+        }
+    }
+}
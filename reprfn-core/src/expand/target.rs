@@ -0,0 +1,81 @@
+//! Per-target values, written `{ macos = "...", windows = "...", default = "..." }`
+
+use proc_macro2::TokenStream as TokenStream2;
+
+#[derive(Clone)]
+pub(crate) struct Targeted {
+    targets: Vec<(syn::Ident, syn::LitStr)>,
+    default: Option<syn::LitStr>,
+}
+
+impl Targeted {
+    pub(crate) fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::braced!(content in input);
+
+        let mut targeted = Targeted { targets: Vec::new(), default: None };
+        while !content.is_empty() {
+            let key: syn::Ident = content.parse()?;
+            content.parse::<syn::Token![=]>()?;
+            let value: syn::LitStr = content.parse()?;
+
+            if key == "default" {
+                if targeted.default.is_some() {
+                    return Err(syn::Error::new(key.span(), "duplicate `default` target"));
+                }
+                targeted.default = Some(value);
+            } else if targeted.targets.iter().any(|(target, _)| *target == key) {
+                return Err(syn::Error::new(key.span(), format!("duplicate target '{}'", key)));
+            } else {
+                targeted.targets.push((key, value));
+            }
+
+            if !content.is_empty() {
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(targeted)
+    }
+
+    /// Emits `attr` once per target, applied through `cfg_attr` so only the matching one takes effect
+    pub(crate) fn cfg_attr_quote(&self, attr: impl Fn(&syn::LitStr) -> TokenStream2) -> TokenStream2 {
+        let predicates = self.targets.iter().map(|(target, _)| predicate(target)).collect::<Vec<_>>();
+        let attrs = self.targets.iter().map(|(_, value)| attr(value));
+
+        let default_quote = if let Some(default) = &self.default {
+            let attr = attr(default);
+            quote::quote! {
+                #[cfg_attr(not(any(#(#predicates),*)), #attr)]
+            }
+        } else {
+            quote::quote! {}
+        };
+
+        quote::quote! {
+            #(#[cfg_attr(#predicates, #attrs)])*
+            #default_quote
+        }
+    }
+}
+
+impl Targeted {
+    /// The `cfg` predicate of every value, `default` applying where no other target does
+    pub(crate) fn predicates(&self) -> Vec<(TokenStream2, &syn::LitStr)> {
+        let mut predicates = self.targets.iter().map(|(target, value)| (predicate(target), value)).collect::<Vec<_>>();
+        if let Some(default) = &self.default {
+            let targets = predicates.iter().map(|(predicate, _)| predicate.clone()).collect::<Vec<_>>();
+            predicates.push((quote::quote! { not(any(#(#targets),*)) }, default));
+        }
+        predicates
+    }
+}
+
+/// `unix` and `windows` name target families, anything else a `target_os`
+fn predicate(target: &syn::Ident) -> TokenStream2 {
+    if target == "unix" || target == "windows" {
+        quote::quote! { #target }
+    } else {
+        let os = syn::LitStr::new(&target.to_string(), target.span());
+        quote::quote! { target_os = #os }
+    }
+}
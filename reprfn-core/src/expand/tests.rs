@@ -0,0 +1,249 @@
+//! Unit tests of the option parsing and of `Builder`, the expansion itself is tested by the doctests of `reprfn`
+
+use quote::ToTokens;
+
+use super::{expand_import_cfg, parse_args, Abi, Builder, Inline};
+
+#[test]
+fn parses_options() {
+    let args = parse_args(quote::quote! { abi = "C", name = "my_add", cold = true, inline = "never" }).unwrap();
+    assert_eq!(args.abi.value().as_deref(), Some("C"));
+    assert_eq!(args.name.map(|name| name.value()).as_deref(), Some("my_add"));
+    assert!(args.cold);
+    assert!(matches!(args.inline, Some((Inline::Never, _))));
+}
+
+#[test]
+fn parses_later_options_over_earlier_ones() {
+    let args = parse_args(quote::quote! { name = "first", name = "second" }).unwrap();
+    assert_eq!(args.name.map(|name| name.value()).as_deref(), Some("second"));
+}
+
+#[test]
+fn tells_unspecified_and_suppressed_abis_apart() {
+    assert!(matches!(parse_args(quote::quote! {}).unwrap().abi, Abi::Unspecified));
+    assert!(matches!(parse_args(quote::quote! { abi = "none" }).unwrap().abi, Abi::None));
+    assert!(matches!(parse_args(quote::quote! { abi = "Rust" }).unwrap().abi, Abi::Explicit(_)));
+}
+
+#[test]
+fn rejects_invalid_abis_with_a_suggestion() {
+    let err = parse_args(quote::quote! { abi = "c" }).err().unwrap();
+    assert!(err.to_string().starts_with("Invalid ABI 'c' (did you mean `C`?)"));
+}
+
+#[test]
+fn rejects_invalid_values() {
+    let err = parse_args(quote::quote! { inline = "sometimes" }).err().unwrap();
+    assert!(err.to_string().starts_with("invalid inline 'sometimes'"));
+    let err = parse_args(quote::quote! { name = "" }).err().unwrap();
+    assert_eq!(err.to_string(), "symbol names cannot be empty");
+}
+
+#[test]
+fn builds_exports() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+    };
+    let expanded = Builder::new(item).abi("C").name("my_add").export().build().unwrap().to_string();
+    assert!(expanded.contains("export_name = \"my_add\""));
+    assert!(expanded.contains("extern \"C\" fn add"));
+}
+
+#[test]
+fn builds_statics_and_extern_blocks() {
+    let item: syn::ItemStatic = syn::parse_quote! {
+        pub static VERSION: u32 = 1;
+    };
+    let expanded = Builder::new(item).name("lib_version").build().unwrap().to_string();
+    assert!(expanded.contains("export_name = \"lib_version\""));
+
+    let item: syn::ItemForeignMod = syn::parse_quote! {
+        extern "C" {
+            pub fn open() -> i32;
+        }
+    };
+    let expanded = Builder::new(item).name("lib_").build().unwrap().to_string();
+    assert!(expanded.contains("link_name = \"lib_open\""));
+}
+
+#[test]
+fn returns_errors() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+    };
+    let err = Builder::new(item).abi("C").arg(quote::quote! { cold = true, inline = "always" }).build().err().unwrap();
+    assert_eq!(err.to_string(), "`cold` and `inline = \"always\"` contradict each other");
+}
+
+#[test]
+fn validate_only_returns_the_item_unchanged() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+    };
+    let expanded = Builder::new(item.clone()).abi("C").arg(quote::quote! { validate_only = true }).build().unwrap();
+    assert_eq!(expanded.to_string(), item.to_token_stream().to_string());
+
+    let err = Builder::new(item).abi("C").arg(quote::quote! { validate_only = true, cold = true, inline = "always" }).build();
+    assert!(err.is_err());
+}
+
+#[test]
+fn versions_a_renamed_definition() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn open() -> i32 {
+            0
+        }
+    };
+    let expanded = Builder::new(item).abi("C").arg(quote::quote! { default_version = "LIB_1.0" }).build().unwrap().to_string();
+    assert!(expanded.contains("export_name = \"open_v\""));
+    assert!(expanded.contains("\".symver {0}, open@@LIB_1.0\""));
+}
+
+#[test]
+fn omits_extern_rust_unless_kept() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn plain() -> u32 {
+            1
+        }
+    };
+    let expanded = Builder::new(item.clone()).abi("Rust").build().unwrap().to_string();
+    assert!(!expanded.contains("extern"));
+    assert!(!expanded.contains("no_mangle"));
+    let expanded = Builder::new(item).abi("Rust").arg(quote::quote! { keep_extern = true }).build().unwrap().to_string();
+    assert!(expanded.contains("extern \"Rust\" fn plain"));
+}
+
+#[test]
+fn notes_since_after_the_docs() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        /// Returns the answer
+        pub fn answer() -> u32 {
+            42
+        }
+    };
+    let expanded = Builder::new(item).abi("C").arg(quote::quote! { since = "1.2.0" }).build().unwrap().to_string();
+    let docs = expanded.find("Returns the answer").unwrap();
+    let note = expanded.find("Available since 1.2.0").unwrap();
+    assert!(docs < note);
+}
+
+#[test]
+fn emits_bare_exports_without_symbol_attributes() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn double(x: u32) -> u32 {
+            x * 2
+        }
+    };
+    let expanded = Builder::new(item).arg(quote::quote! { bare = true }).build().unwrap().to_string();
+    assert!(!expanded.contains("extern"));
+    assert!(!expanded.contains("no_mangle"));
+    assert!(!expanded.contains("export_name"));
+}
+
+#[test]
+fn links_with_kind_and_modifiers() {
+    let item = syn::Item::Verbatim(quote::quote! {
+        pub fn open() -> i32;
+    });
+    let expanded = Builder::new(item)
+        .abi("C")
+        .arg(quote::quote! { link = { name = "foo", kind = "static", modifiers = "+whole-archive,-bundle" } })
+        .build()
+        .unwrap()
+        .to_string();
+    assert!(expanded.contains("link (name = \"foo\" , kind = \"static\" , modifiers = \"+whole-archive,-bundle\")"));
+}
+
+#[test]
+fn imports_by_ordinal() {
+    let item = syn::Item::Verbatim(quote::quote! {
+        pub fn beep(freq: u32, duration: u32) -> i32;
+    });
+    let expanded = Builder::new(item)
+        .abi("system")
+        .arg(quote::quote! { link = { name = "kernel32", kind = "raw-dylib", import_name_type = "undecorated" }, ordinal = 3 })
+        .build()
+        .unwrap()
+        .to_string();
+    assert!(expanded.contains("kind = \"raw-dylib\" , import_name_type = \"undecorated\""));
+    assert!(expanded.contains("link_ordinal (3)"));
+}
+
+#[test]
+fn checks_without_exporting() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+    };
+    let expanded = Builder::new(item).abi("C").name("my_add").arg(quote::quote! { check_only = true }).build().unwrap().to_string();
+    assert!(expanded.contains("extern \"C\" fn add"));
+    assert!(!expanded.contains("no_mangle"));
+    assert!(!expanded.contains("export_name"));
+}
+
+#[test]
+fn exports_and_imports_per_feature() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+    };
+    let expanded = Builder::new(item).abi("C").arg(quote::quote! { mode = { export = "build-lib", import = "build-app" } }).build().unwrap().to_string();
+    assert!(expanded.contains("# [cfg (feature = \"build-lib\")] # [no_mangle] pub extern \"C\" fn add"));
+    assert!(expanded.contains("# [cfg (all (feature = \"build-app\" , not (feature = \"build-lib\")))] pub fn add (a : i32 , b : i32) -> i32 ;"));
+}
+
+#[test]
+fn exports_with_wasm_bindgen_on_wasm32() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn is_even(#[reprfn(as = "u8")] strict: bool, x: u32) -> bool {
+            !strict || x % 2 == 0
+        }
+    };
+    let expanded = Builder::new(item).abi("C").arg(quote::quote! { wasm_bindgen = true }).build().unwrap().to_string();
+    assert!(expanded.contains("# [cfg (target_arch = \"wasm32\")] # [:: wasm_bindgen :: prelude :: wasm_bindgen] pub fn is_even (strict : bool , x : u32)"));
+    assert!(expanded.contains("# [cfg (not (target_arch = \"wasm32\"))]"));
+}
+
+#[test]
+fn documents_after_the_written_docs() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        /// Written on the function
+        pub fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+    };
+    let expanded = Builder::new(item).abi("C").arg(quote::quote! { doc = "First added", doc = "Second added" }).build().unwrap().to_string();
+    let written = expanded.find("Written on the function").unwrap();
+    let first = expanded.find("First added").unwrap();
+    let second = expanded.find("Second added").unwrap();
+    assert!(written < first && first < second);
+}
+
+#[test]
+fn imports_under_the_import_cfg() {
+    let item: syn::ItemFn = syn::parse_quote! {
+        pub fn add(a: i32, b: i32) -> i32 {
+            a + b
+        }
+    };
+    let expanded = expand_import_cfg(parse_args(quote::quote! { abi = "C" }).unwrap(), item).unwrap().to_string();
+    assert!(expanded.contains("# [cfg (not (reprfn_import))] # [no_mangle] pub extern \"C\" fn add"));
+    assert!(expanded.contains("# [cfg (reprfn_import)] pub fn add (a : i32 , b : i32) -> i32 ;"));
+}
+
+#[test]
+fn rejects_unknown_options_with_a_suggestion() {
+    let err = parse_args(quote::quote! { no_mangel = "always" }).err().unwrap();
+    assert_eq!(err.to_string(), "unsupported reprfn property 'no_mangel' (did you mean `no_mangle`?)");
+    let err = parse_args(quote::quote! { sparkle = true }).err().unwrap();
+    assert_eq!(err.to_string(), "unsupported reprfn property 'sparkle'");
+}
@@ -0,0 +1,317 @@
+//! Classification of the ABIs accepted by `reprfn`, and the signatures each of them allows
+
+use proc_macro2::TokenStream as TokenStream2;
+
+use super::{defaults, Abi, Args, Func, Mode, NoMangle};
+
+/// ABIs using the Rust calling convention, these keep Rust symbol mangling and allow generics
+pub(crate) const RUST_ABIS: [&str; 3] = ["Rust", "rust-call", "rust-intrinsic"];
+
+/// ABIs accepting C-variadic arguments (`...`)
+pub(crate) const VARIADIC_ABIS: [&str; 9] = [
+    "C", "C-unwind", "cdecl", "system", "system-unwind", "aapcs", "win64", "sysv64", "efiapi"
+];
+
+pub(crate) fn is_rust(abi: &str) -> bool {
+    RUST_ABIS.contains(&abi)
+}
+
+pub(crate) fn supports_variadic(abi: &str) -> bool {
+    VARIADIC_ABIS.contains(&abi)
+}
+
+/// Arm TrustZone ABIs, the ABI replaces the former `#[cmse_nonsecure_entry]` attribute
+pub(crate) const CMSE_ENTRY_ABI: &str = "C-cmse-nonsecure-entry";
+pub(crate) const CMSE_CALL_ABI: &str = "C-cmse-nonsecure-call";
+
+/// Arguments of a non-secure entry are passed in `r0`-`r3` only
+pub(crate) const CMSE_MAX_PARAMS: usize = 4;
+
+/// Interrupt handler ABIs, entered by the hardware rather than called
+pub(crate) const INTERRUPT_ABIS: [&str; 6] = [
+    "msp430-interrupt", "x86-interrupt", "avr-interrupt", "avr-non-blocking-interrupt", "riscv-interrupt-m",
+    "riscv-interrupt-s",
+];
+
+pub(crate) fn is_interrupt(abi: &str) -> bool {
+    INTERRUPT_ABIS.contains(&abi)
+}
+
+/// The parameters an interrupt handler takes, x86 passes the interrupt stack frame and, for some exceptions,
+/// an error code while the others pass nothing
+pub(crate) fn interrupt_params(abi: &str) -> std::ops::RangeInclusive<usize> {
+    if abi == "x86-interrupt" { 1..=2 } else { 0..=0 }
+}
+
+/// CUDA kernels, launched from the host over a grid of threads
+pub(crate) const PTX_KERNEL_ABI: &str = "ptx-kernel";
+
+/// ABIs whose functions return nothing to their caller
+pub(crate) fn returns_unit(abi: &str) -> bool {
+    is_interrupt(abi) || abi == PTX_KERNEL_ABI
+}
+
+/// The accepted ABIs by category, with a note on when to use each category
+pub(crate) const ABI_GROUPS: [(&str, &str, &[&str]); 5] = [
+    ("calling conventions", "for functions called from other languages", &[
+        "C", "C-unwind", "cdecl", "stdcall", "stdcall-unwind", "fastcall", "vectorcall", "thiscall",
+        "thiscall-unwind", "aapcs", "win64", "sysv64", "efiapi", "system", "system-unwind",
+    ]),
+    ("interrupt handlers", "for functions installed in an interrupt vector", &INTERRUPT_ABIS),
+    ("Rust-internal", "keep Rust symbol mangling and generics", &[
+        "Rust", "rust-call", "rust-intrinsic", "platform-intrinsic", "unadjusted",
+    ]),
+    ("platform-specific", "for Arm TrustZone, CUDA and WebAssembly targets", &[
+        "C-cmse-nonsecure-call", "C-cmse-nonsecure-entry", "ptx-kernel", "wasm",
+    ]),
+    ("no ABI", "for a plain `fn`", &["none"]),
+];
+
+/// Levenshtein distance between two ABI names
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Edits tolerated between a mistyped ABI and its suggestion
+const MAX_DISTANCE: usize = 2;
+
+/// The closest accepted ABI to a mistyped one, if it is near enough to be a typo
+pub(crate) fn closest<'a>(abi: &str, abis: &[&'a str]) -> Option<&'a str> {
+    abis.iter()
+        // Casing mistakes like "Cdecl" or "SYSTEM" count as the closest possible typo
+        .map(|candidate| (distance(&abi.to_lowercase(), &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// All accepted ABIs, one category per line
+pub(crate) fn table() -> String {
+    ABI_GROUPS.iter().map(|(group, note, abis)| {
+        let abis = abis.iter().map(|abi| format!("\"{}\"", abi)).collect::<Vec<_>>();
+        format!("\n  {} ({}): {}", group, note, abis.join(", "))
+    }).collect()
+}
+
+/// The ABI of the function, from `abi`, the ABI it is declared with or the crate defaults
+pub(crate) fn resolve(args: &mut Args, source_abi: Option<syn::Abi>, ident: &syn::Ident) -> syn::Result<()> {
+    // Keep the ABI the function was declared with unless `abi` overrides it
+    if let Some(source_abi) = source_abi {
+        let span = source_abi.extern_token.span;
+        let source_abi_value = source_abi.name.unwrap_or_else(|| syn::LitStr::new("C", span));
+        match &args.abi {
+            Abi::Unspecified => args.abi = Abi::Explicit(source_abi_value),
+            Abi::Explicit(abi_value) if abi_value.value() == source_abi_value.value() => {},
+            _ if args.override_abi => {},
+            _ => {
+                let message = format!("Function is declared `extern \"{}\"`, set `override_abi = true` to change its ABI", source_abi_value.value());
+                return Err(syn::Error::new(source_abi_value.span(), message));
+            },
+        }
+    }
+
+    // Rust crates agreeing on a symbol, named like C ones but keeping the Rust ABI
+    if let Some(internal_abi) = &args.internal_abi {
+        match &args.abi {
+            Abi::Explicit(abi_value) if !is_rust(&abi_value.value()) => {
+                let message = format!("`internal_abi` uses the Rust ABI, the function is declared with '{}'", abi_value.value());
+                return Err(syn::Error::new(internal_abi.span(), message));
+            },
+            Abi::None => return Err(syn::Error::new(internal_abi.span(), "`internal_abi` cannot be used with `abi = \"none\"`")),
+            _ => args.abi = Abi::Explicit(syn::LitStr::new("Rust", internal_abi.span())),
+        }
+    }
+
+    // Explicit attributes, and the ABI the function is declared with, win over the crate defaults
+    if let (Abi::Unspecified, Some(default_abi)) = (&args.abi, &defaults::get().abi) {
+        args.abi = if default_abi == "none" { Abi::None } else { Abi::Explicit(syn::LitStr::new(default_abi, ident.span())) };
+    }
+    Ok(())
+}
+
+/// UEFI entry points are `extern "efiapi" fn(ImageHandle, *mut SystemTable) -> Status`
+pub(crate) fn uefi(args: &mut Args, func: &Func) -> syn::Result<()> {
+    if !args.uefi {
+        return Ok(());
+    }
+    let ident = &func.sig.ident;
+    match &args.abi {
+        Abi::Unspecified => args.abi = Abi::Explicit(syn::LitStr::new("efiapi", ident.span())),
+        Abi::Explicit(abi_value) if abi_value.value() == "efiapi" => {},
+        _ => return Err(syn::Error::new_spanned(ident, "`uefi` entry points must use the \"efiapi\" ABI")),
+    }
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new_spanned(ident, "`uefi` can only be used on exported functions"));
+    }
+    let inputs = &func.sig.inputs;
+    if inputs.len() != 2 {
+        let span = func.sig.paren_token.span.join();
+        return Err(syn::Error::new(span, format!("UEFI entry points take an image handle and a system table, found {} parameters", inputs.len())));
+    }
+    if let syn::ReturnType::Default = func.sig.output {
+        return Err(syn::Error::new_spanned(ident, "UEFI entry points must return a status"));
+    }
+    Ok(())
+}
+
+/// What the ABI puts on the exported signature, checked once the hints rewrote it
+pub(crate) fn check_signature(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let abi_value = args.abi.value();
+    let rust_abi = args.abi.is_rust();
+    let syn::Signature { ident, generics, inputs, variadic, output, .. } = &func.sig;
+
+    // Lifetimes erase and are kept, a single unmangled symbol cannot stand for every instance of a type parameter
+    if let (Mode::Export, false) = (&func.mode, rust_abi) {
+        if let Some(param) = generics.params.iter().find(|param| !matches!(param, syn::GenericParam::Lifetime(_))) {
+            let message = "Exports with a non-Rust ABI can only be generic over lifetimes, use the Rust ABI with `instances` to export instances of a generic function";
+            return Err(syn::Error::new_spanned(param, message));
+        }
+    }
+
+    // The caller location is passed as a hidden argument, which only the Rust ABI knows about
+    if let Some(track_caller) = &args.track_caller {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new(track_caller.span(), "`track_caller` can only be used on exported functions"));
+        }
+        if !rust_abi {
+            return Err(syn::Error::new(track_caller.span(), "`track_caller` can only be used with the Rust ABI"));
+        }
+    }
+
+    if let Some(variadic) = variadic {
+        if !abi_value.as_deref().is_some_and(supports_variadic) {
+            return Err(syn::Error::new_spanned(variadic, format!("Variadic arguments are only supported by the ABIs {:?}", VARIADIC_ABIS)));
+        }
+    }
+
+    // Secure code is entered through exports, calls into non-secure code only go through function pointers
+    match abi_value.as_deref() {
+        Some(CMSE_CALL_ABI) => {
+            let message = format!("`{}` only applies to function pointers, use the `callback` hint on a parameter instead", CMSE_CALL_ABI);
+            return Err(syn::Error::new_spanned(ident, message));
+        },
+        Some(CMSE_ENTRY_ABI) => {
+            if let Mode::Import = func.mode {
+                return Err(syn::Error::new_spanned(ident, format!("`{}` can only be used on exported functions", CMSE_ENTRY_ABI)));
+            }
+            if inputs.len() > CMSE_MAX_PARAMS {
+                let message = format!("`{}` functions take at most {} parameters, all passed in registers", CMSE_ENTRY_ABI, CMSE_MAX_PARAMS);
+                return Err(syn::Error::new_spanned(inputs, message));
+            }
+        },
+        // A wrong signature otherwise only fails in codegen, with errors that do not point at the handler
+        Some(interrupt_abi) if is_interrupt(interrupt_abi) => {
+            let expected = interrupt_params(interrupt_abi);
+            if !expected.contains(&inputs.len()) {
+                let message = if *expected.end() == 0 {
+                    format!("`{}` handlers cannot take parameters", interrupt_abi)
+                } else {
+                    format!("`{}` handlers take {} to {} parameters", interrupt_abi, expected.start(), expected.end())
+                };
+                return Err(if inputs.is_empty() { syn::Error::new_spanned(ident, message) } else { syn::Error::new_spanned(inputs, message) });
+            }
+        },
+        _ => {},
+    }
+
+    // Nothing receives the return value of a handler or a kernel launch
+    if let (Some(unit_abi), syn::ReturnType::Type(_, ty)) = (abi_value.as_deref(), output) {
+        if returns_unit(unit_abi) && !matches!(&**ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty()) {
+            let message = if unit_abi == PTX_KERNEL_ABI {
+                format!("`{}` kernels must return `()`, write results through a pointer parameter", unit_abi)
+            } else {
+                format!("`{}` handlers must return `()`", unit_abi)
+            };
+            return Err(syn::Error::new_spanned(ty, message));
+        }
+    }
+
+    if args.track_caller.is_some() {
+        func.attrs.push(syn::parse_quote! { #[track_caller] });
+    }
+    Ok(())
+}
+
+/// The `extern` of the declaration, and of the export where it is spelled out
+pub(crate) fn quote(args: &Args, func: &Func) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let abi_value = args.abi.value();
+
+    // Generated tokens borrow the spans of the source so downstream errors point at the user's code
+    let abi_quote = match &args.abi {
+        Abi::Explicit(abi_value) => quote::quote_spanned! { abi_value.span()=>
+            extern #abi_value
+        },
+        Abi::Unspecified => quote::quote_spanned! { func.sig.fn_token.span=>
+            extern "Rust"
+        },
+        Abi::None => quote::quote! {},
+    };
+
+    // A bare export is a plain Rust `fn`, anything that would add an ABI or symbol attributes contradicts it
+    if let Some(bare) = &args.bare {
+        if let Mode::Import = func.mode {
+            return Err(syn::Error::new(bare.span(), "`bare` can only be used on exported functions"));
+        }
+        if matches!(args.abi, Abi::None) || !matches!(abi_value.as_deref(), None | Some("Rust")) {
+            return Err(syn::Error::new(bare.span(), "`bare` can only be used with the Rust ABI"));
+        }
+        if args.keep_extern {
+            return Err(syn::Error::new(bare.span(), "`bare` and `keep_extern` contradict each other"));
+        }
+        if args.name.is_some() || args.name_targets.is_some() {
+            return Err(syn::Error::new(bare.span(), "`bare` functions keep their Rust name, `name` cannot be used"));
+        }
+        if let Some((NoMangle::Always | NoMangle::Release | NoMangle::Debug, policy_value)) = &args.no_mangle {
+            return Err(syn::Error::new(policy_value.span(), "`bare` functions keep their mangled name"));
+        }
+    }
+
+    // A plain `fn` already is `extern "Rust"`, only spell it out on exports when asked to
+    let export_abi_quote = match (&args.abi, args.keep_extern) {
+        (Abi::None, true) => {
+            return Err(syn::Error::new_spanned(&func.sig.ident, "`keep_extern` cannot be used with `abi = \"none\"`"));
+        },
+        (Abi::Unspecified, false) => quote::quote! {},
+        (_, false) if abi_value.as_deref() == Some("Rust") => quote::quote! {},
+        _ => abi_quote.clone(),
+    };
+    Ok((abi_quote, export_abi_quote))
+}
+
+/// Importing through the implicit `extern "Rust"` is almost never intended
+pub(crate) fn check_import(args: &Args, func: &Func) -> syn::Result<()> {
+    let Mode::Import = func.mode else {
+        return Ok(());
+    };
+    let ident = &func.sig.ident;
+    if !args.abi.is_explicit() {
+        let span = ident.span();
+        return Err(syn::Error::new(span, format!("Missing ABI for imported function '{}', use `abi = \"Rust\"` to import through the Rust ABI", ident)));
+    }
+    if let Abi::None = args.abi {
+        let span = ident.span();
+        return Err(syn::Error::new(span, "Imported functions always have an ABI, `abi = \"none\"` cannot be used here"));
+    }
+
+    // Extern blocks only take lifetimes, which may still be bounded in a `where` clause
+    let message = "Imported functions can only be generic over lifetimes, extern blocks cannot declare type or const parameters";
+    let generics = &func.sig.generics;
+    if let Some(param) = generics.params.iter().find(|param| !matches!(param, syn::GenericParam::Lifetime(_))) {
+        return Err(syn::Error::new_spanned(param, message));
+    }
+    if let Some(predicate) = generics.where_clause.iter().flat_map(|clause| &clause.predicates).find(|predicate| !matches!(predicate, syn::WherePredicate::Lifetime(_))) {
+        return Err(syn::Error::new_spanned(predicate, message));
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Inspection of the types crossing the ABI boundary
+
+use super::{Args, Func, Mode};
+
+/// Rejects the types that obviously have no C representation
+pub(crate) fn check_ffi_safe(ty: &syn::Type) -> syn::Result<()> {
+    let reason = match ty {
+        syn::Type::Tuple(tuple) if !tuple.elems.is_empty() => "tuples",
+        syn::Type::Slice(_) => "slices",
+        syn::Type::TraitObject(_) => "trait objects",
+        syn::Type::ImplTrait(_) => "`impl Trait` types",
+        syn::Type::Reference(reference) => return check_ffi_safe_pointee(&reference.elem),
+        syn::Type::Ptr(pointer) => return check_ffi_safe_pointee(&pointer.elem),
+        syn::Type::Paren(ty) => return check_ffi_safe(&ty.elem),
+        syn::Type::Group(ty) => return check_ffi_safe(&ty.elem),
+        _ => return Ok(()),
+    };
+    Err(syn::Error::new_spanned(ty, format!("{} have no C representation", reason)))
+}
+
+/// Pointers and references to unsized types are fat and have no C representation either
+fn check_ffi_safe_pointee(ty: &syn::Type) -> syn::Result<()> {
+    match ty {
+        syn::Type::Slice(_) | syn::Type::TraitObject(_) => {
+            Err(syn::Error::new_spanned(ty, "pointers to unsized types have no C representation"))
+        },
+        syn::Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => {
+            Err(syn::Error::new_spanned(ty, "pointers to unsized types have no C representation"))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// `T` of a `Result<T, E>`
+pub(crate) fn result_ok(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Result")?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 2 => match args.args.first() {
+            Some(syn::GenericArgument::Type(ty)) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `T` of a `Box<T>`
+pub(crate) fn boxed(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Box")?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+            Some(syn::GenericArgument::Type(ty)) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The `core::ffi` alias matching a primitive, for types spelled the Rust way in a C signature
+fn ffi_alias(ty: &syn::Type) -> Option<&'static str> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let ident = path.path.get_ident()?;
+    let alias = match ident.to_string().as_str() {
+        "i8" => "c_schar",
+        "u8" => "c_uchar",
+        "i16" => "c_short",
+        "u16" => "c_ushort",
+        "i32" => "c_int",
+        "u32" => "c_uint",
+        "i64" => "c_longlong",
+        "u64" => "c_ulonglong",
+        "f32" => "c_float",
+        "f64" => "c_double",
+        _ => return None,
+    };
+    Some(alias)
+}
+
+struct DeepAbi<'a> {
+    abi: &'a syn::LitStr,
+}
+
+impl syn::visit_mut::VisitMut for DeepAbi<'_> {
+    fn visit_type_bare_fn_mut(&mut self, bare_fn: &mut syn::TypeBareFn) {
+        // Pointers spelling out their ABI keep it, only plain `fn` ones are rust ABI by omission
+        if bare_fn.abi.is_none() {
+            let abi = self.abi;
+            bare_fn.abi = Some(syn::parse_quote! { extern #abi });
+        }
+        syn::visit_mut::visit_type_bare_fn_mut(self, bare_fn);
+    }
+}
+
+/// Gives every `fn` pointer without an ABI in `ty`, however deeply nested, the ABI `abi`
+fn deep_abi(ty: &mut syn::Type, abi: &syn::LitStr) {
+    syn::visit_mut::VisitMut::visit_type_mut(&mut DeepAbi { abi }, ty);
+}
+
+/// Struct and enum types passed by value, guessed from an uppercase last segment as types are not resolved yet
+fn is_user_type(ty: &syn::Type, generics: &[&syn::Ident]) -> bool {
+    match ty {
+        syn::Type::Paren(ty) => is_user_type(&ty.elem, generics),
+        syn::Type::Group(ty) => is_user_type(&ty.elem, generics),
+        syn::Type::Path(path) if path.qself.is_none() => {
+            let Some(segment) = path.path.segments.last() else {
+                return false;
+            };
+            let name = segment.ident.to_string();
+            // Nullable pointers are laid out like the pointers they wrap
+            let known = ["Option", "NonNull", "Self", "PhantomData", "ManuallyDrop", "MaybeUninit"];
+            name.starts_with(|c: char| c.is_ascii_uppercase())
+                && !known.contains(&name.as_str())
+                && !(path.path.segments.len() == 1 && generics.contains(&&segment.ident))
+        },
+        _ => false,
+    }
+}
+
+/// `deep_abi`, after the hints so `callback` and `deep_abi` agree on the parameters they both touch
+pub(crate) fn apply_deep_abi(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let Some(deep_abi_value) = &args.deep_abi else {
+        return Ok(());
+    };
+    let Some(abi_lit) = args.abi.lit() else {
+        return Err(syn::Error::new(deep_abi_value.span(), "`deep_abi` needs an explicit `abi`"));
+    };
+    for input in &mut func.sig.inputs {
+        if let syn::FnArg::Typed(arg) = input {
+            deep_abi(&mut arg.ty, abi_lit);
+        }
+    }
+    if let syn::ReturnType::Type(_, ty) = &mut func.sig.output {
+        deep_abi(ty, abi_lit);
+    }
+    Ok(())
+}
+
+/// The parameter types and the return type of the exported signature
+fn signature_types(sig: &syn::Signature) -> impl Iterator<Item = &syn::Type> {
+    let output_ty = match &sig.output {
+        syn::ReturnType::Type(_, ty) => Some(&**ty),
+        syn::ReturnType::Default => None,
+    };
+    sig.inputs.iter().filter_map(|input| match input {
+        syn::FnArg::Typed(arg) => Some(&*arg.ty),
+        syn::FnArg::Receiver(_) => None,
+    }).chain(output_ty)
+}
+
+/// A `#[repr(Rust)]` struct passed by value compiles fine and silently disagrees with C on its layout
+pub(crate) fn check_repr_c(args: &Args, func: &mut Func) -> syn::Result<()> {
+    let Some(repr_c_check) = &args.repr_c_check else {
+        return Ok(());
+    };
+    if let Mode::Import = func.mode {
+        return Err(syn::Error::new(repr_c_check.span(), "`repr_c_check` can only be used on exported functions"));
+    }
+    let generic_idents = func.sig.generics.params.iter().filter_map(|param| match param {
+        syn::GenericParam::Type(param) => Some(&param.ident),
+        _ => None,
+    }).collect::<Vec<_>>();
+    let feature_quote = &func.feature_quote;
+    for ty in signature_types(&func.sig).filter(|ty| is_user_type(ty, &generic_idents)) {
+        func.aux.extend(quote::quote_spanned! { syn::spanned::Spanned::span(ty)=>
+            #feature_quote
+            const _: () = {
+                fn repr_c_check<T: ::reprfn_core::ReprC + ?Sized>() {}
+                let _ = repr_c_check::<#ty>;
+            };
+        });
+    }
+    Ok(())
+}
+
+/// Reported through a deprecated item, the only warning a macro can raise on stable
+pub(crate) fn suggest_ffi_types(args: &Args, func: &mut Func) {
+    if !args.suggest_ffi_types || args.abi.is_rust() {
+        return;
+    }
+    let feature_quote = &func.feature_quote;
+    for ty in signature_types(&func.sig) {
+        if let Some(alias) = ffi_alias(ty) {
+            let note = format!("`{}` is spelled `core::ffi::{}` in C signatures", quote::quote!(#ty), alias);
+            func.aux.extend(quote::quote_spanned! { syn::spanned::Spanned::span(ty)=>
+                #feature_quote
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    const SUGGEST_FFI_TYPE: () = ();
+                    SUGGEST_FFI_TYPE
+                };
+            });
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! `#[repr(C)]` tables of function pointers, handed to C as a single registration
+
+use proc_macro2::TokenStream as TokenStream2;
+
+/// `reprfn_vtable! { pub struct PluginApi { fn init(); fn run(x: u32) -> u32; } }`
+pub(crate) struct Vtable {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    ident: syn::Ident,
+    entries: Vec<syn::ForeignItemFn>,
+}
+
+impl syn::parse::Parse for Vtable {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<syn::Token![struct]>()?;
+        let ident = input.parse()?;
+        let content;
+        syn::braced!(content in input);
+        let mut entries = Vec::new();
+        while !content.is_empty() {
+            entries.push(content.parse()?);
+        }
+        Ok(Vtable { attrs, vis, ident, entries })
+    }
+}
+
+impl Vtable {
+    /// The struct, and a `new` filling every field with the function of the same name in scope
+    pub(crate) fn quote(&self) -> syn::Result<TokenStream2> {
+        let Vtable { attrs, vis, ident, entries } = self;
+
+        let mut fields = Vec::new();
+        let mut idents = Vec::new();
+        for entry in entries {
+            let sig = &entry.sig;
+            if let Some(param) = sig.generics.params.first() {
+                return Err(syn::Error::new_spanned(param, "function pointers cannot be generic"));
+            }
+            if let Some(variadic) = &sig.variadic {
+                return Err(syn::Error::new_spanned(variadic, "variadic functions cannot be stored in a table"));
+            }
+
+            // Entries declared without an ABI are `extern "C"`, like the table C reads them from
+            let abi = match &sig.abi {
+                Some(syn::Abi { name: Some(abi), .. }) => super::validate_abi(abi.clone())?,
+                Some(abi) => syn::LitStr::new("C", abi.extern_token.span),
+                None => syn::LitStr::new("C", sig.ident.span()),
+            };
+            if super::traits::is_rust(&abi.value()) || abi.value() == "none" {
+                return Err(syn::Error::new(abi.span(), "tables are read from C, use a non-Rust ABI"));
+            }
+
+            let mut arg_types = Vec::new();
+            for input in &sig.inputs {
+                match input {
+                    syn::FnArg::Typed(arg) => arg_types.push(&arg.ty),
+                    syn::FnArg::Receiver(receiver) => {
+                        return Err(syn::Error::new_spanned(receiver, "function pointers cannot take `self`"));
+                    },
+                }
+            }
+
+            let entry_attrs = &entry.attrs;
+            let unsafety = &sig.unsafety;
+            let output = &sig.output;
+            let field = &sig.ident;
+            fields.push(quote::quote! {
+                #(#entry_attrs)*
+                #vis #field: #unsafety extern #abi fn(#(#arg_types),*) #output
+            });
+            idents.push(field);
+        }
+
+        let new_doc = format!("The `{}` of the functions of the same names in scope", ident);
+        Ok(quote::quote! {
+            #(#attrs)*
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            #vis struct #ident {
+                #(#fields,)*
+            }
+
+            impl #ident {
+                #[doc = #new_doc]
+                #vis const fn new() -> Self {
+                    #ident {
+                        #(#idents,)*
+                    }
+                }
+            }
+
+            impl ::core::default::Default for #ident {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        })
+    }
+}
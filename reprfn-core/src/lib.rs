@@ -0,0 +1,112 @@
+//! The parts of `reprfn` that do not need the proc macro, for build scripts and companion tooling
+
+#[cfg(feature = "expand")]
+pub mod expand;
+
+#[cfg(feature = "expand")]
+pub use expand::Builder;
+
+/// Every ABI accepted by the `abi` attribute of `reprfn`
+pub const ABIS: [&str; 31] = [
+    "Rust", "C", "C-unwind", "C-cmse-nonsecure-call", "C-cmse-nonsecure-entry", "cdecl", "rust-call",
+    "stdcall", "stdcall-unwind", "fastcall", "vectorcall", "thiscall", "thiscall-unwind", "aapcs",
+    "win64", "sysv64", "ptx-kernel", "msp430-interrupt", "x86-interrupt", "efiapi", "avr-interrupt",
+    "avr-non-blocking-interrupt", "riscv-interrupt-m", "riscv-interrupt-s", "wasm", "system",
+    "system-unwind", "rust-intrinsic", "platform-intrinsic", "unadjusted", "none"
+];
+
+/// Whether `reprfn` accepts `abi` as the value of its `abi` attribute
+///
+/// ```
+/// assert!(reprfn_core::is_valid_abi("C-unwind"));
+/// assert!(!reprfn_core::is_valid_abi("c"));
+/// ```
+pub fn is_valid_abi(abi: &str) -> bool {
+    ABIS.contains(&abi)
+}
+
+/// The code an error is reported to C with by functions exported with `result_abi`
+///
+/// `0` reports success, so every error must map to a nonzero code.
+///
+/// ```
+/// enum ParseError {
+///     Empty,
+///     Invalid,
+/// }
+///
+/// impl reprfn_core::ErrorCode for ParseError {
+///     fn error_code(&self) -> i32 {
+///         match self {
+///             ParseError::Empty => 1,
+///             ParseError::Invalid => 2,
+///         }
+///     }
+/// }
+/// ```
+pub trait ErrorCode {
+    fn error_code(&self) -> i32;
+}
+
+/// The value an export with `errno` returns on error, next to the code stored for `last_error`
+pub trait ErrorSentinel {
+    fn error_sentinel() -> Self;
+}
+
+macro_rules! impl_error_sentinel {
+    ($($int:ty)*) => {
+        $(
+            /// `-1`, or all bits set for unsigned types like `(size_t)-1` in C
+            impl ErrorSentinel for $int {
+                fn error_sentinel() -> Self {
+                    !0
+                }
+            }
+        )*
+    };
+}
+
+impl_error_sentinel!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+impl<T> ErrorSentinel for *const T {
+    fn error_sentinel() -> Self {
+        core::ptr::null()
+    }
+}
+
+impl<T> ErrorSentinel for *mut T {
+    fn error_sentinel() -> Self {
+        core::ptr::null_mut()
+    }
+}
+
+impl ErrorSentinel for () {
+    fn error_sentinel() -> Self {}
+}
+
+std::thread_local! {
+    static LAST_ERROR: core::cell::Cell<i32> = const { core::cell::Cell::new(0) };
+}
+
+/// The code of the last error returned by an export with `errno` on this thread, `0` if there was none
+///
+/// Like `errno`, the code is not reset by successful calls.
+pub fn last_error() -> i32 {
+    LAST_ERROR.with(core::cell::Cell::get)
+}
+
+/// Stores the code read by `last_error`, called by the code `reprfn` generates
+#[doc(hidden)]
+pub fn set_last_error(code: i32) {
+    LAST_ERROR.with(|last_error| last_error.set(code));
+}
+
+/// Types with a C layout, required of the types exports with `repr_c_check` take and return by value
+///
+/// Derived with `#[derive(reprfn::ReprC)]`, which only accepts `#[repr(C)]` and `#[repr(transparent)]` types and,
+/// for enums, integer representations.
+///
+/// # Safety
+///
+/// The type must have a layout C agrees on, which `#[repr(Rust)]` types do not.
+pub unsafe trait ReprC {}
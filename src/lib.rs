@@ -1,62 +1,241 @@
-#![allow(unused)]
 extern crate proc_macro;
-extern crate proc_macro2;
-extern crate quote;
-extern crate syn;
 
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
-
-const ABIS: [&str; 31] = [
-    "Rust", "C", "C-unwind", "C-cmse-nonsecure-call", "C-cmse-nonsecure-entry", "cdecl", "rust-call",
-    "stdcall", "stdcall-unwind", "fastcall", "vectorcall", "thiscall", "thiscall-unwind", "aapcs",
-    "win64", "sysv64", "ptx-kernel", "msp430-interrupt", "x86-interrupt", "efiapi", "avr-interrupt",
-    "avr-non-blocking-interrupt", "riscv-interrupt-m", "riscv-interrupt-s", "wasm", "system",
-    "system-unwind", "rust-intrinsic", "platform-intrinsic", "unadjusted", "none"
-];
-
-fn valid_abi(abi: &str) -> bool {                               
-    ABIS.contains(&abi)
-}
-
-fn validate_abi(abi: syn::LitStr) -> Result<syn::LitStr, syn::Error> {
-    let raw = abi.value();
-    if valid_abi(&raw) {
-        Ok(abi)
-    } else {
-        let span = abi.span();
-        Err(syn::Error::new(span, format!("Invalid ABI '{}', expecting one of {:?}", raw, ABIS)))
-    }
-}
-
-fn opt_lit_as_opt_val(opt: Option<&syn::LitStr>) -> Option<String> {
-    let val = opt?;
-    Some(val.value())
-}
-
-enum Mode {
-    Export,
-    Import,
-}
+use reprfn_core::expand;
 
 /// Macro attribute `reprfn`:
 ///
 /// This macro transforms a function into an ABI-compliant external function or an imported one.
 ///
 /// # Attributes:
-/// * `abi`: Optional. Defines the ABI of the function. If omitted, the default ABI is used.
-///          If set to `none`, no specific ABI is enforced. Supported ABIs include "C", "Rust", "stdcall", etc.
-/// * `name`: Optional. Sets the exported name of the function in C-like linkers. Defaults to the Rust function name.
+/// * `abi`: Optional. Defines the ABI of the function. If omitted, the ABI the function is declared with
+///   (`extern "C" fn`) is kept, otherwise the default Rust ABI is used and, as with `abi = "Rust"`, the symbol
+///   name stays mangled. Functions with any other ABI keep their lifetime parameters but cannot be generic over
+///   types or constants.
+///   If set to `none`, no specific ABI is enforced and a plain `fn` is exported. Supported ABIs include
+///   "C", "Rust", "stdcall", etc.
+///   Imports must always name their ABI explicitly, `abi = "Rust"` included.
+///   `C-cmse-nonsecure-entry` is limited to exports of at most four parameters, `C-cmse-nonsecure-call` only
+///   applies to function pointers through the `callback` hint. Interrupt handlers take no parameters, except
+///   `x86-interrupt` ones which take the stack frame and possibly an error code, and must return `()` like
+///   `ptx-kernel` kernels.
+/// * `override_abi`: Optional. Changing the ABI of a function already declared `extern "..."` is an error,
+///   unless this is set to `true`.
+/// * `abi` also accepts an ABI per target on exports, `abi = { windows = "system", default = "C" }`, with the
+///   same keys as `name`. One function is generated per target, all calling a single copy of the body.
+/// * `name`: Optional. Sets the exported name of the function in C-like linkers, or the name an import links
+///   against. Defaults to the Rust function name. Empty names and names containing NUL bytes, control characters or
+///   whitespace are rejected. Exports also accept a name per target, `name = { macos = "_foo", default = "foo" }`,
+///   where `unix` and `windows` match target families, any other key a `target_os` and `default` all remaining
+///   targets.
+/// * `link_name`: Optional. The name of an imported function's symbol, like `name` on imports. A symbol version can
+///   be referenced on ELF targets as `link_name = "memcpy@GLIBC_2.2.5"`, passed through verbatim. Versions are
+///   validated on both `link_name` and `name` of imports, and `sym@@VERSION`, which only libraries define, is an error.
+/// * `feature`: Optional. Only compiles the function when the given cargo feature is enabled, or disabled
+///   when the feature name is prefixed with `!`.
+///   Imports wrapped by a Rust function can gate a single item, `feature = { on = "ffi", applies_to = "wrapper" }`
+///   gating the wrapper of `import_style = "item"` or `swappable` only. With `applies_to = "export"`, a `swappable`
+///   import keeps its wrapper and setter without the feature, panicking when called before a replacement is set.
+///   `applies_to = "all"` is the default.
+/// * `link`: Optional. Links the named native library for an imported function, emitting `#[link(name = "...")]`.
+///   The kind and modifiers of the library are given as `link = { name = "foo", kind = "static", modifiers =
+///   "+whole-archive" }`, where `kind` is one of `dylib`, `static`, `framework` or `raw-dylib`.
+/// * `ordinal`: Optional. Imports the function by ordinal from a `raw-dylib` library, emitting `#[link_ordinal(N)]`.
+///   `link` then also accepts the `import_name_type` of the library.
+/// * `require_link`: Optional. If set to `true` on an imported function, keeps a reference to the symbol
+///   in a `#[used]` static so a missing symbol becomes a link error instead of a runtime failure.
+/// * `then`: Optional. Path of another attribute macro applied to the generated function, as its outermost attribute.
+/// * `accessor`: Optional. On an exported static, also exports a function with the given name returning the
+///   address of the static. The function uses `abi` as its ABI, "C" if omitted.
+/// * `keep_extern`: Optional. Exports using the Rust ABI are emitted as a plain `fn`, since `extern "Rust"` is
+///   redundant there. If set to `true`, the `extern "Rust"` annotation is kept instead.
+/// * `validate_only`: Optional. If set to `true`, all attributes are validated and errors reported as usual, but
+///   the item is emitted unchanged.
+/// * `tuple_return`: Optional. On an exported function returning a tuple, generates a `#[repr(C)]` struct with
+///   the given name and fields `_0`, `_1`, ... and returns it in place of the tuple.
+/// * `safe`: Optional. If set to `true` on an imported function, declares it `safe` in an `unsafe extern` block
+///   so it can be called without `unsafe`. On an `unsafe extern` block, applies to all of its items. Imports are
+///   always emitted in `unsafe extern` blocks and are otherwise unsafe to call, and `safe` on an `unsafe fn` is
+///   an error.
+/// * `module`: Optional. Places the generated items in an inline module of the given name, with the visibility
+///   of the function, to keep them out of the surrounding namespace. Symbol names are not affected. The items
+///   remain visible from the scope the function is declared in, a private function becomes `pub(super)`.
+/// * `reexport_as`: Optional. Re-exports the function under the given name with a `use` of the function's
+///   visibility, `reexport_as = add` emitting `use self::mylib_add as add;`. With `module`, the re-export sits
+///   outside the module, reaching the function through it.
+/// * `internal_abi`: Optional. If set to `true`, the function keeps the Rust ABI but gets a stable symbol, its own
+///   name or `name`, emitted as `#[export_name]` on exports and `#[link_name]` in an `extern "Rust"` block on
+///   imports. Meant for Rust crates of one workspace linking against each other by symbol, which must be built
+///   with the same compiler. Cannot be combined with a non-Rust `abi`.
+/// * `rust_wrapper`: Optional. If set to `true` on an export with a non-Rust ABI, generates an `#[inline]`
+///   `<ident>_rust` function with the Rust ABI and the exported signature, forwarding its arguments to the export.
+/// * `uefi`: Optional. If set to `true`, checks the function has the shape of a UEFI entry point, taking an image
+///   handle and a system table and returning a status. The ABI defaults to "efiapi".
+/// * `multiversion`: Optional. List of target features, `multiversion = ["avx2", "sse2", "baseline"]`. Compiles
+///   the body once per feature with `#[target_feature]` and makes the export call the first one detected at
+///   runtime on x86, falling back to the `baseline` build. Requires `std`.
+/// * `ctor`, `dtor`: Optional. If set to `true`, registers the exported function to run when the binary is loaded
+///   or unloaded, through `.init_array`/`.fini_array` on ELF, `__mod_init_func`/`__mod_term_func` on Mach-O and
+///   `.CRT$XCU`/`.CRT$XTU` on Windows. The function must use the "C" ABI, take no parameters and return `()`.
+/// * `instances`: Optional. On a generic function using the Rust ABI, exports an `extern "C"` trampoline per listed
+///   type, `instances = ["u8", "u32"]` exporting `ident_u8` and `ident_u32`. Functions with several type parameters
+///   take a tuple per instance, `instances = ["(u8, f32)"]` exporting `ident_u8_f32`.
+/// * `cold`: Optional. If set to `true`, marks the function `#[cold]`, for error paths rarely called.
+/// * `inline`: Optional. One of `always`, `never` or `hint`, emitting `#[inline(always)]`, `#[inline(never)]` or
+///   `#[inline]` on an exported function. `cold` cannot be combined with `always`.
+/// * `assert_params`: Optional. Fails the build unless the function takes exactly this many parameters, as a guard
+///   against changing an ABI contract by accident.
+/// * `debug_trace`: Optional. If set to `true`, the exported function prints a line to stderr when it is entered and
+///   left, in debug builds only. Requires `std`.
+/// * `status_enum`: Optional. Status codes, `status_enum = { Ok = 0, Err = 1 }`, generated as a `#[repr(C)]`
+///   enum named `Status`, or the name before the braces, that the exported function returns. The return type
+///   can be left out, any type but the enum is rejected.
+/// * `string_return`: Optional. If set to `cstring`, an exported function returning a `String` returns it as a
+///   `*mut c_char` instead, null if the string contains a NUL byte. The string must be passed back to the
+///   generated `<name>_free` function to be freed.
+/// * `opaque`: Optional. If set to `true`, an exported function returning a `Box<T>` returns it as a `*mut T`
+///   handle with `Box::into_raw`. The handle must be passed back to the generated `<name>_destroy` function, which
+///   drops it with `Box::from_raw` and ignores null.
+/// * `result_abi`: Optional. If set to `true`, an exported function returning a `Result<T, E>` returns an `i32`
+///   code instead, `0` on success with the value written to a trailing `out: *mut T` parameter unless it is null,
+///   or the code given by the `reprfn_core::ErrorCode` implementation of `E`. The function is then `unsafe`, a
+///   non-null `out` must be valid for writes. `Result<(), E>` adds no parameter and stays safe.
+///   Requires a dependency on `reprfn-core`.
+/// * `errno`: Optional. If set to `true`, an exported function returning a `Result<T, E>` returns `T` instead.
+///   Errors return `-1`, all bits set for unsigned types, or null for pointers and store the code given by the
+///   `reprfn_core::ErrorCode` implementation of `E` for `reprfn_core::last_error`. A path to a `fn(i32)`,
+///   `errno = "crate::set_errno"`, stores the code elsewhere. Requires a dependency on `reprfn-core`.
+/// * `return_via_ptr`: Optional. If set to `true`, the exported function returns its value through a trailing
+///   `out: *mut T` parameter instead, for C APIs expecting an out-pointer. The function is then `unsafe`, `out`
+///   must be valid for writes and debug builds assert it is not null.
+/// * `cfg`: Optional. Any `cfg` predicate, `cfg = "all(unix, target_pointer_width = \"64\")"`, forwarded as is to
+///   a `#[cfg]` on the function. Combines with `feature`.
+/// * `naked`: Optional. If set to `true`, the exported function is `#[unsafe(naked)]`, its body must then be a
+///   single `naked_asm!` invocation.
+/// * `naked_interrupt`: Optional. If set to `true`, as `naked` for an interrupt handler, the `abi` must be one of the
+///   interrupt ABIs. The assembly is responsible for saving registers and returning with the interrupt return
+///   instruction of the target, such as `iret` or `reti`.
+/// * `import_style`: Optional. `block`, the default, declares an imported function in an extern block. `item`
+///   declares it under a hidden name and wraps it in a regular function, which other attributes apply to as
+///   usual. Rust has no other way to declare an import, so an extern block is emitted either way.
+/// * `doc`: Optional. A line of documentation added to the function after its own, `doc` may be given several times
+///   for several lines.
+/// * `since`: Optional. A version, `since = "1.2.0"`, noted as "Available since 1.2.0" at the end of the
+///   documentation of the function.
+/// * `wasm_bindgen`: Optional. If set to `true`, the function is exported with `#[wasm_bindgen]` on `wasm32`, as a
+///   plain Rust function without parameter hints, and as usual on every other target. The crate then needs a
+///   `wasm-bindgen` dependency for `wasm32`.
+/// * `hidden`: Optional. If set to `true`, the function is `#[doc(hidden)]`, on exports and imports alike.
+/// * `suggest_ffi_types`: Optional. If set to `true`, warns about primitive types in the signature of a non-Rust
+///   ABI export that have a `core::ffi` alias, `i32` that could be `c_int` and so on.
+/// * `emit_header`: Optional. If set to `true`, the equivalent C prototype is added to the documentation of
+///   the function. Fails on types without an obvious C counterpart, other types are assumed to be `#[repr(C)]`
+///   types declared in C under the same name.
+/// * `default_version`: Optional. An ELF version node, `default_version = "LIB_1.0"`, made the default version of
+///   the exported symbol. The function is defined as `name_v` and versioned with `.symver name_v, name@@LIB_1.0`,
+///   as the assembler needs a symbol distinct from the versioned one. The node must be declared in the version
+///   script the library is linked with. Other targets export `name` unversioned.
+/// * `panic_hook`: Optional. If set to `true`, the function is the setter C calls to register a panic callback.
+///   It takes a single `Option<extern "C" fn(*const u8, usize)>` parameter and no body, which is generated to
+///   store the callback. A `<name>_invoke(message: &str) -> bool` function is generated alongside, for the panic
+///   handler of the crate to pass the message to the callback, if one is registered.
+/// * `pointer_width`: Optional. Only compiles the function for targets with the given pointer width, one of
+///   `16`, `32` or `64`. Combines with `feature` and `cfg`.
+/// * `track_caller`: Optional. If set to `true`, the exported function is `#[track_caller]`. Only the Rust ABI
+///   can pass the caller location.
+/// * `no_mangle`: Optional. Overrides whether the exported function is `#[no_mangle]`, which otherwise follows
+///   the ABI. `always` and `never` apply to every build, `release` only to builds without `debug_assertions` and
+///   `debug` only to builds with them. An `export_name` from `name` follows the same policy, and `never` rejects
+///   `name`.
+/// * `bare`: Optional. If set to `true`, the exported function is guaranteed to be emitted as a plain Rust `fn`,
+///   without `extern`, `#[no_mangle]` or `#[export_name]`. Options that would add any of them are rejected.
+/// * `check_only`: Optional. If set to `true`, the exported function keeps its ABI, so rustc checks its
+///   signature for FFI safety, but is neither `#[no_mangle]` nor `#[export_name]`, so no symbol is exported yet.
+///   Unlike `validate_only`, the generated code is kept.
+/// * `swappable`: Optional. If set to `true`, the import is called through `<IDENT>_PTR`, a private `AtomicPtr`,
+///   which the `unsafe` `set_<ident>(Some(f))` points at a replacement and `set_<ident>(None)` back at the linked
+///   symbol. The replacement must uphold the contract of the import, which safe code can call with `safe`.
+/// * `unstable_attr`: Optional. The name of an attribute emitted as is, `unstable_attr = "rustc_std_internal_symbol"`
+///   emits `#[rustc_std_internal_symbol]`. **Meant for compiler-internal attributes, these need a nightly compiler
+///   and internal features, are not checked by `reprfn` and may change or vanish in any Rust release.**
+/// * `repr_c_check`: Optional. If set to `true`, the structs and enums the export takes or returns by value must
+///   implement `reprfn_core::ReprC`, derived with `#[derive(ReprC)]` on `#[repr(C)]` types only. Types are recognized
+///   by their name starting with an uppercase letter, aliases of structs or lowercase type names are not checked.
+/// * `assert_abi_layout`: Optional. The expected size in bytes of parameters of the exported signature and of its
+///   return type, `assert_abi_layout = { ctx = 8, len = 4, return = 4 }`. Any other size fails the build, combined
+///   with `pointer_width` or `cfg` it guards the layout on the targets the sizes were written for.
+/// * `register_in`: Optional. The name of a linker section, `register_in = "COMMANDS"`, a pointer to the exported
+///   function is placed in. `section_slice!("COMMANDS", extern "C" fn())` returns all the functions registered in
+///   it, which must share that signature.
+/// * `hash_suffix`: Optional. If set to `true`, a short hash of the name and version of the crate is appended to
+///   the symbol, `foo` is exported as `foo_a1b2c3`, so plugins of different versions can be loaded together. The
+///   symbol is available as `<IDENT>_SYMBOL`.
+/// * `module_prefix`: Optional. The module path of the function, `module_prefix = "net::tcp"`, as the macro
+///   cannot see it. Without a `name` the symbol becomes `net_tcp_<ident>`, otherwise a `{module}` placeholder in
+///   `name` is replaced with `net_tcp`.
+/// * `from_name_convention`: Optional. Derives the linked name of an import from its `snake_case` Rust name, one of
+///   `camelCase`, `PascalCase`, `SCREAMING_SNAKE_CASE` or `LibCamel`, where `sdl_create_window` links against
+///   `Sdl_CreateWindow`. Names that would not convert back unchanged, like `sha_256`, are rejected.
+/// * `prefix`: Optional. Prepended to the name of the function to form its symbol when no `name` is given,
+///   `prefix = "mylib_"` exports `fn init` as `mylib_init`. Only unmangled symbols are prefixed. If set to `none`,
+///   the prefix of `reprfn_defaults` is not applied. Prefixes are checked like `name`.
+/// * `ffi_pure`, `ffi_const`: Optional. If set to `true`, the import is declared `#[ffi_pure]`, it has no side
+///   effects, or `#[ffi_const]`, its result only depends on its arguments. Both need nightly features.
+/// * `no_sanitize`: Optional. A sanitizer or list of sanitizers, `no_sanitize = ["address", "thread"]`, the export
+///   is not instrumented for. Emits `#[no_sanitize(...)]`, which needs the `no_sanitize` nightly feature.
+/// * `dedup`: Optional. If set to `true`, the export is emitted `#[linkage = "linkonce_odr"]` in a section of its
+///   own, `.text.<symbol>` on ELF and `.text$rf_<symbol>` on COFF, so the linker keeps a single copy of identical
+///   definitions. Needs the `linkage` nightly feature, and fails the build on Mach-O and wasm targets.
+/// * `anon`: Optional. If set to `true`, the exported function is emitted inside `const _: () = { ... };`, its
+///   symbol is defined but no Rust item is visible. The symbol must be unmangled.
+/// * `deep_abi`: Optional. If set to `true`, every `fn` pointer type without an ABI in the parameters and return
+///   type, including those nested in other function pointers, is given the ABI of the function.
+/// * `underscore_alias`: Optional. If set to `true`, the exported symbol is also defined with a leading
+///   underscore, `_name` next to `name` as C sees them.
 /// * `mode`: Optional. If set to `export`, it marks the function for external export. If set to `import`,
-///           it marks the function as externally imported. If omitted, the macro will automatically infer the mode
-///           based on the presence or absence of a function body (presence implies export, absence implies import).
+///   it marks the function as externally imported. If omitted, the macro will automatically infer the mode
+///   based on the presence or absence of a function body (presence implies export, absence implies import).
+///   If set to `forward`, the function is exported with a body calling the symbol named by `import_name`,
+///   which is imported with the same signature.
+/// * `mode` also accepts a feature per mode, `mode = { export = "build-lib", import = "build-app" }`. The function
+///   is exported when the first feature is enabled and imported when only the second one is, for crates that both
+///   provide and consume the symbol.
+///   An inferred export becomes an import when the crate is built with `--cfg reprfn_import` and opted in with
+///   `reprfn_defaults(import_cfg = true)`. An explicit `mode` always takes precedence over the cfg.
+/// * `import_name`: Required with `mode = "forward"`. The symbol the exported function forwards to, checked like
+///   `name`.
+///
+/// Parameters of exported functions accept hints of their own:
+/// * `#[reprfn(as = "u8")]`: Passes the parameter as the given type and converts it back on entry,
+///   `bool` parameters compare against zero while other types use an `as` cast.
+/// * `#[reprfn(callback)]`: Gives a function pointer parameter, possibly wrapped in `Option`, the ABI of the function.
+/// * `#[reprfn(nullable)]`: Passes an `Option<&T>` parameter as a `*const T`, and `Option<&mut T>` as a `*mut T`,
+///   where null is `None`. The function is then `unsafe`, a non-null pointer must be valid for the reference.
+/// * `#[reprfn(slice_abi)]`: Passes a `&[T]` or `&mut [T]` parameter `x` as a pointer `x_ptr` followed by a
+///   length `x_len`. The function is then `unsafe`, `x_ptr` must be valid for `x_len` elements, though any
+///   pointer including null is accepted for a zero length.
+/// * `#[reprfn(repr_int = "u32")]`: Passes an enum parameter as the given integer type and converts it back with
+///   its `TryFrom` implementation. The function is then `unsafe`, the integer must be a valid discriminant. Debug
+///   builds assert the conversion succeeds, passing an invalid discriminant is undefined behavior otherwise.
+/// * `#[reprfn(out)]`: Marks a raw pointer parameter as written to, debug builds assert it is not null on entry.
+///
+/// Applied to a `static`, the item is exported under its own name or `name` unless it has no value, `static X: T;`,
+/// or `mode = "import"` is given, in which case it is declared in an extern block of its `abi` instead, which
+/// imports must name like functions.
+///
+/// Applied to a `trait`, every default method is also exported as a free function under its own name, as if the
+/// attribute was applied to it. Default methods cannot take `self` or refer to `Self`.
+///
+/// Applied to an `extern` block, `abi` replaces the ABI of the block while `feature`, `cfg`, `pointer_width` and
+/// `link` apply to all of its items and `name` is used as a prefix for the linked name of every item.
 ///
 /// # Example:
 ///
 /// ```
+/// # use reprfn::reprfn;
 /// #[reprfn(abi = "C", name = "my_c_function")]
 /// pub fn my_function() {
-///     // Function body
+///     println!("called from C");
 /// }
 /// ```
 ///
@@ -66,165 +245,208 @@ enum Mode {
 /// #[no_mangle]
 /// #[export_name = "my_c_function"]
 /// pub extern "C" fn my_function() {
-///     // Function body
+///     println!("called from C");
 /// }
 /// ```
+///
+/// Imports may be declared without a body, here the C `abs` is called as `c_abs` from Rust:
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(mode = "import", abi = "C", name = "abs")]
+/// pub fn c_abs(x: i32) -> i32;
+///
+/// assert_eq!(unsafe { c_abs(-3) }, 3);
+/// ```
+///
+/// `const` and `unsafe` exports keep their qualifiers, in the order Rust expects them:
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub const unsafe fn my_const_function(x: u32) -> u32 {
+///     x * 2
+/// }
+///
+/// const DOUBLED: u32 = unsafe { my_const_function(21) };
+/// assert_eq!(DOUBLED, 42);
+/// ```
+///
+/// Rust crates linked together can share a symbol without going through C, Rust types included:
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(internal_abi = true)]
+/// pub fn engine_name() -> String {
+///     "engine".to_string()
+/// }
+///
+/// // In the crate linking against it
+/// #[reprfn(internal_abi = true, name = "engine_name")]
+/// fn linked_engine_name() -> String;
+///
+/// assert_eq!(unsafe { linked_engine_name() }, "engine");
+/// ```
 #[proc_macro_attribute]
 pub fn reprfn(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mut abi = None::<syn::LitStr>;
-    let mut name = None::<syn::LitStr>;
-    let mut feature = None::<syn::LitStr>;
-    let mut no_mangle = true;
-    let mut support_generics = false;
-    let mut mode = None::<Mode>;
-
-    let parser = syn::meta::parser(|meta| {
-        if meta.path.is_ident("abi") {
-            let value = validate_abi(meta.value()?.parse()?)?;
-            abi = if value.value() == "none" {
-                None
-            } else {
-                Some(value)
-            };
-            no_mangle = {
-                let check = opt_lit_as_opt_val(abi.as_ref());
-                match check {
-                    Some(val) if val == "Rust" => false,
-                    Some(val) if val == "rust-call" => false,
-                    Some(val) if val == "rust-intrinsic" => false,
-                    _ => true,
-                }
-            };
-            support_generics = {
-                let check = opt_lit_as_opt_val(abi.as_ref());
-                match check {
-                    Some(val) if val == "Rust" => true,
-                    Some(val) if val == "rust-call" => true,
-                    Some(val) if val == "rust-intrinsic" => true,
-                    _ => false,
-                }
-            };
-        } else if meta.path.is_ident("name") {
-            let value: syn::LitStr = meta.value()?.parse()?;
-            name = if value.value() == "none" {
-                None
-            } else {
-                Some(value)
-            };
-        } else if meta.path.is_ident("mode") {
-            let value: syn::LitStr = meta.value()?.parse()?;
-            mode = if value.value() == "none" {
-                None
-            } else if value.value() == "import" {
-                Some(Mode::Import)
-            } else if value.value() == "export" {
-                Some(Mode::Export)
-            } else {
-                return Err(meta.error(format!("invalid mode '{}', expecting one of '['none', 'import', 'export']'", value.value())));
-            }
-        } else if meta.path.is_ident("feature") {
-            let value: syn::LitStr = meta.value()?.parse()?;
-            feature = if value.value() == "none" {
-                None
-            } else {
-                Some(value)
-            };
-        }
-        Ok(())
-    });
-
-    syn::parse_macro_input!(attr with parser);
-    let input = syn::parse_macro_input!(item as syn::ItemFn);
-
-    let abi_quote = if let Some(abi_value) = abi {
-        quote::quote! {
-            extern #abi_value
-        }
-    } else {
-        quote::quote! {
-            extern "Rust"
-        }
-    };
-
-    let name_quote = if let Some(name_value) = name {
-        quote::quote! {
-            #[export_name = #name_value]
-        }
-    } else {
-        quote::quote! {}
-    };
-
-    let no_mangle_quote = if no_mangle {
-        quote::quote! {
-            #[no_mangle]
-        }
-    } else {
-        quote::quote! {}
-    };
+    TokenStream::from(expand::reprfn(attr.into(), item.into()))
+}
 
-    let feature_quote = if let Some(feature_value) = feature {
-        quote::quote! {
-            #[cfg(feature = #feature_value)]
-        }
-    } else {
-        quote::quote! {}
-    };
+/// Derive macro `ReprC`:
+///
+/// Implements `reprfn_core::ReprC` for `#[repr(C)]` and `#[repr(transparent)]` structs and unions, and for enums with
+/// a `C` or integer representation, erroring on any other type. Exports with `repr_c_check` require it of the types
+/// they pass by value.
+///
+/// # Example:
+///
+/// ```
+/// # use reprfn::{reprfn, ReprC};
+/// #[derive(ReprC)]
+/// #[repr(C)]
+/// pub struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// #[reprfn(abi = "C", repr_c_check = true)]
+/// pub fn norm(point: Point) -> f64 {
+///     (point.x * point.x + point.y * point.y).sqrt()
+/// }
+/// ```
+#[proc_macro_derive(ReprC)]
+pub fn derive_repr_c(item: TokenStream) -> TokenStream {
+    TokenStream::from(expand::derive_repr_c(item.into()))
+}
 
-    let syn::ItemFn { attrs, vis, sig, block } = input;
-    let syn::Signature { constness, unsafety, fn_token, ident, inputs, variadic, output, generics, .. } = sig;
-    let syn::Generics { lt_token, params, gt_token, where_clause } = generics;
+/// Macro `section_slice`:
+///
+/// The functions registered with `register_in` in a section, as a `&'static [T]` of their function pointer type
+/// `T`. The order of the functions is up to the linker.
+///
+/// # Example:
+///
+/// ```
+/// # use reprfn::{reprfn, section_slice};
+/// #[reprfn(abi = "C", register_in = "COMMANDS")]
+/// pub fn help() -> u32 {
+///     1
+/// }
+///
+/// #[reprfn(abi = "C", register_in = "COMMANDS")]
+/// pub fn quit() -> u32 {
+///     2
+/// }
+///
+/// let commands: &[extern "C" fn() -> u32] = section_slice!("COMMANDS", extern "C" fn() -> u32);
+/// let mut codes = commands.iter().map(|command| command()).collect::<Vec<_>>();
+/// codes.sort();
+/// assert_eq!(codes, [1, 2]);
+/// ```
+#[proc_macro]
+pub fn section_slice(input: TokenStream) -> TokenStream {
+    TokenStream::from(expand::section_slice(input.into()))
+}
 
-    // Determine mode if not provided, based on the presence of a block or a semicolon
-    let inferred_mode = if let Some(_) = mode {
-        mode.unwrap()
-    } else if block.stmts.is_empty() {
-        Mode::Import
-    } else {
-        Mode::Export
-    };
+/// Macro `reprfn_mono`:
+///
+/// A generic function using the Rust ABI, followed by `for` and the list of its instances, exporting the same
+/// `extern "C"` trampolines as the `instances` attribute of `reprfn` without an attribute per function. Functions
+/// with several type parameters take a tuple per instance, `(u8, f32)` exporting `ident_u8_f32`.
+///
+/// # Example:
+///
+/// ```
+/// reprfn::reprfn_mono! {
+///     pub fn scale<T: core::ops::Mul<Output = T>>(value: T, factor: T) -> T {
+///         value * factor
+///     }
+///     for [f32, f64, u32]
+/// }
+///
+/// assert_eq!(scale_f64(1.5, 2.0), 3.0);
+/// assert_eq!(scale_u32(3, 4), 12);
+/// ```
+#[proc_macro]
+pub fn reprfn_mono(input: TokenStream) -> TokenStream {
+    TokenStream::from(expand::reprfn_mono(input.into()))
+}
 
-    let expanded = match inferred_mode {
-        Mode::Export => {
-            if support_generics {
-                quote::quote! {
-                    #(#attrs)*
-                    #feature_quote
-                    #name_quote
-                    #no_mangle_quote
-                    #vis #constness #unsafety #abi_quote #fn_token #ident #lt_token #params #gt_token(#inputs #variadic) #output #where_clause #block
-                }
-            } else {
-                quote::quote! {
-                    #(#attrs)*
-                    #feature_quote
-                    #name_quote
-                    #no_mangle_quote
-                    #vis #constness #unsafety #abi_quote #fn_token #ident(#inputs #variadic) #output #block
-                }
-            }
-        },
-        Mode::Import => {
-            if support_generics {
-                quote::quote! {
-                    #abi_quote {
-                        #(#attrs)*
-                        #feature_quote
-                        #name_quote
-                        #vis #fn_token #ident #lt_token #params #gt_token(#inputs #variadic) #output #where_clause #block
-                    }
-                }
-            } else {
-                quote::quote! {
-                    #abi_quote {
-                        #(#attrs)*
-                        #feature_quote
-                        #name_quote
-                        #vis #fn_token #ident(#inputs #variadic) #output #block
-                    }
-                }
-            }
-        },
-    };
+/// Macro `reprfn_vtable`:
+///
+/// A `#[repr(C)]` struct of function pointers, declared like the functions of an extern block, and a `const fn new`
+/// filling each field with the function of the same name in scope. Entries without an ABI are `extern "C"`, the
+/// functions they are filled with have to match their signature exactly.
+///
+/// # Example:
+///
+/// ```
+/// use reprfn::{reprfn, reprfn_vtable};
+///
+/// #[reprfn(abi = "C")]
+/// pub fn version() -> u32 {
+///     2
+/// }
+///
+/// #[reprfn(abi = "C")]
+/// pub fn run(x: u32) -> u32 {
+///     x * 2
+/// }
+///
+/// reprfn_vtable! {
+///     pub struct PluginApi {
+///         fn version() -> u32;
+///         /// Doubles its argument
+///         fn run(x: u32) -> u32;
+///     }
+/// }
+///
+/// let api = PluginApi::new();
+/// assert_eq!((api.version)(), 2);
+/// assert_eq!((api.run)(21), 42);
+/// ```
+#[proc_macro]
+pub fn reprfn_vtable(input: TokenStream) -> TokenStream {
+    TokenStream::from(expand::reprfn_vtable(input.into()))
+}
 
-    TokenStream::from(expanded)
+/// Macro attribute `reprfn_defaults`:
+///
+/// Sets the defaults of every `#[reprfn]` expanded after it in the crate, in source order. The item it is applied
+/// to is left unchanged, so it is usually put on the first item of the crate root.
+///
+/// # Attributes:
+/// * `abi`: Optional. The ABI of the functions that neither set `abi` nor are declared with one.
+/// * `prefix`: Optional. The `prefix` of the functions that do not set one.
+/// * `import_cfg`: Optional. If set to `true`, every function inferred to be an export is declared as an import
+///   instead when the crate is built with `--cfg reprfn_import`, so one crate can serve as both the implementation
+///   and the declarations of a library. Functions with an explicit `mode` are left alone. The cfg has to be declared
+///   with `[lints.rust] unexpected_cfgs = { check-cfg = ['cfg(reprfn_import)'] }` to avoid warnings.
+///
+/// An attribute given to `#[reprfn]` always takes precedence over the default, and a later `reprfn_defaults`
+/// replaces the defaults of an earlier one.
+///
+/// # Example:
+///
+/// ```
+/// #[reprfn::reprfn_defaults(abi = "C", prefix = "mylib_")]
+/// use reprfn::reprfn;
+///
+/// #[reprfn]
+/// pub fn version() -> u32 {
+///     1
+/// }
+///
+/// extern "C" {
+///     fn mylib_version() -> u32;
+/// }
+///
+/// assert_eq!(unsafe { mylib_version() }, 1);
+/// ```
+#[proc_macro_attribute]
+pub fn reprfn_defaults(attr: TokenStream, item: TokenStream) -> TokenStream {
+    TokenStream::from(expand::reprfn_defaults(attr.into(), item.into()))
 }
+
+#[cfg(doctest)]
+mod tests;
@@ -0,0 +1,1865 @@
+//! Doctests of attribute combinations, those expected to compile and the errors of those that must not
+
+/// `require_link` on an item style import links the declaration behind the wrapper, not the Rust wrapper
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", import_style = "item", name = "abs", require_link = true)]
+/// pub fn c_abs(x: i32) -> i32;
+///
+/// assert_eq!(unsafe { c_abs(-3) }, 3);
+/// ```
+struct RequireLinkItem;
+
+/// `require_link` on a swappable import links the declaration the pointer falls back to
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", swappable = true, name = "abs", require_link = true)]
+/// pub fn c_abs(x: i32) -> i32;
+///
+/// assert_eq!(unsafe { c_abs(-3) }, 3);
+/// ```
+struct RequireLinkSwappable;
+
+/// Raw identifiers lose their `r#` in the names generated from them
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", swappable = true, name = "abs")]
+/// pub fn r#type(x: i32) -> i32;
+///
+/// unsafe { set_type(None) };
+/// assert!(TYPE_PTR.load(core::sync::atomic::Ordering::Relaxed).is_null());
+/// assert_eq!(unsafe { r#type(-3) }, 3);
+/// ```
+struct SwappableRawIdent;
+
+/// An item style import of a raw identifier links against the name without `r#`
+///
+/// ```
+/// #[reprfn::reprfn(abi = "C")]
+/// pub fn r#loop() -> u32 {
+///     1
+/// }
+///
+/// mod imports {
+///     use reprfn::reprfn;
+///
+///     #[reprfn(abi = "C", import_style = "item")]
+///     pub fn r#loop() -> u32;
+/// }
+///
+/// fn main() {
+///     assert_eq!(unsafe { imports::r#loop() }, 1);
+/// }
+/// ```
+struct ItemStyleRawIdent;
+
+/// The crate default prefix goes in front of a raw identifier without its `r#`
+///
+/// ```
+/// #[reprfn::reprfn_defaults(abi = "C", prefix = "rawlib_")]
+/// use reprfn::reprfn;
+///
+/// #[reprfn]
+/// pub fn r#type() -> u32 {
+///     1
+/// }
+///
+/// extern "C" {
+///     fn rawlib_type() -> u32;
+/// }
+///
+/// assert_eq!(unsafe { rawlib_type() }, 1);
+/// ```
+struct DefaultPrefixRawIdent;
+
+/// `module_prefix` joins the module path to a raw identifier without its `r#`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", module_prefix = "net::tcp")]
+/// pub fn r#move() -> u32 {
+///     1
+/// }
+///
+/// extern "C" {
+///     fn net_tcp_move() -> u32;
+/// }
+///
+/// assert_eq!(unsafe { net_tcp_move() }, 1);
+/// ```
+struct ModulePrefixRawIdent;
+
+/// The stable symbol of an `internal_abi` raw identifier has no `r#`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(internal_abi = true)]
+/// pub fn r#ref() -> String {
+///     "ref".to_string()
+/// }
+///
+/// #[reprfn(internal_abi = true, name = "ref")]
+/// fn linked_ref() -> String;
+///
+/// assert_eq!(unsafe { linked_ref() }, "ref");
+/// ```
+struct InternalAbiRawIdent;
+
+/// Options deriving names from the symbol, like `underscore_alias`, see a raw identifier without its `r#`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", underscore_alias = true)]
+/// pub fn r#box() -> u32 {
+///     1
+/// }
+///
+/// extern "C" {
+///     fn _box() -> u32;
+/// }
+///
+/// fn main() {
+///     assert_eq!(unsafe { _box() }, 1);
+/// }
+/// ```
+struct UnderscoreAliasRawIdent;
+
+/// The prefix of an extern block goes in front of raw identifiers without their `r#`
+///
+/// ```
+/// #[reprfn::reprfn(abi = "C")]
+/// pub fn blk_dyn() -> u32 {
+///     1
+/// }
+///
+/// mod imports {
+///     use reprfn::reprfn;
+///
+///     #[reprfn(name = "blk_")]
+///     extern "C" {
+///         pub fn r#dyn() -> u32;
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(unsafe { imports::r#dyn() }, 1);
+/// }
+/// ```
+struct ExternBlockRawIdent;
+
+/// The symbol of a raw identifier export with `hash_suffix` has no `r#` either
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", hash_suffix = true)]
+/// pub fn r#loop() -> u32 {
+///     1
+/// }
+///
+/// assert!(LOOP_SYMBOL.starts_with("loop_"));
+/// assert_eq!(r#loop(), 1);
+/// ```
+struct HashSuffixRawIdent;
+
+/// Raw identifiers can be registered in a section
+///
+/// ```
+/// # use reprfn::{reprfn, section_slice};
+/// #[reprfn(abi = "C", register_in = "RAW")]
+/// pub fn r#match() -> u32 {
+///     1
+/// }
+///
+/// let entries: &[extern "C" fn() -> u32] = section_slice!("RAW", extern "C" fn() -> u32);
+/// assert_eq!(entries.len(), 1);
+/// ```
+struct RegisterInRawIdent;
+
+/// A raw identifier can be a `panic_hook` setter
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", panic_hook = true)]
+/// pub fn r#use(hook: Option<extern "C" fn(*const u8, usize)>);
+///
+/// r#use(None);
+/// assert!(!use_invoke("boom"));
+/// ```
+struct PanicHookRawIdent;
+
+/// Instances of a raw identifier are named without its `r#`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Rust", instances = ["u8"])]
+/// pub fn r#move<T: Copy>(value: T) -> T {
+///     value
+/// }
+///
+/// assert_eq!(move_u8(3), 3);
+/// ```
+struct InstancesRawIdent;
+
+/// `as` passes a parameter as another type, C calls the export with the integer it sees
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn hint_as(#[reprfn(as = "u8")] on: bool, #[reprfn(as = "u32")] small: u8) -> u32 {
+///     if on { small as u32 } else { 0 }
+/// }
+///
+/// extern "C" {
+///     #[link_name = "hint_as"]
+///     fn c_hint_as(on: u8, small: u32) -> u32;
+/// }
+///
+/// assert_eq!(unsafe { c_hint_as(2, 7) }, 7);
+/// assert_eq!(unsafe { c_hint_as(0, 7) }, 0);
+/// assert_eq!(unsafe { c_hint_as(1, 0x1ff) }, 0xff);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn hint_as(#[reprfn(as = "u8")] (on, _): (bool, bool)) -> bool {
+///     on
+/// }
+/// ```
+struct HintAs;
+
+/// `callback` gives a function pointer parameter the ABI of the function, `Option` included
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn hint_callback(#[reprfn(callback)] f: fn(u32) -> u32, #[reprfn(callback)] g: Option<fn(u32) -> u32>, x: u32) -> u32 {
+///     g.map_or(f(x), |g| g(f(x)))
+/// }
+///
+/// extern "C" fn double(x: u32) -> u32 {
+///     x * 2
+/// }
+///
+/// extern "C" fn inc(x: u32) -> u32 {
+///     x + 1
+/// }
+///
+/// extern "C" {
+///     #[link_name = "hint_callback"]
+///     fn c_hint_callback(f: extern "C" fn(u32) -> u32, g: Option<extern "C" fn(u32) -> u32>, x: u32) -> u32;
+/// }
+///
+/// assert_eq!(hint_callback(double, Some(inc), 3), 7);
+/// assert_eq!(unsafe { c_hint_callback(double, None, 3) }, 6);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn hint_callback(#[reprfn(callback)] f: u32) -> u32 {
+///     f
+/// }
+/// ```
+struct HintCallback;
+
+/// `out` marks a raw pointer the export writes to
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub unsafe fn hint_out(x: u32, #[reprfn(out)] result: *mut u32) {
+///     unsafe { *result = x * 3 };
+/// }
+///
+/// extern "C" {
+///     #[link_name = "hint_out"]
+///     fn c_hint_out(x: u32, result: *mut u32);
+/// }
+///
+/// let mut result = 0;
+/// unsafe { c_hint_out(2, &mut result) };
+/// assert_eq!(result, 6);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn hint_out(x: u32, #[reprfn(out)] result: &mut u32) {
+///     *result = x * 3;
+/// }
+/// ```
+struct HintOut;
+
+/// `return_via_ptr` makes the export `unsafe`, the caller vouches for `out`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", return_via_ptr = true)]
+/// pub fn pair() -> [u32; 2] {
+///     [1, 2]
+/// }
+///
+/// let mut out = [0; 2];
+/// unsafe { pair(&mut out) };
+/// assert_eq!(out, [1, 2]);
+/// ```
+///
+/// ```compile_fail,E0133
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", return_via_ptr = true)]
+/// pub fn pair() -> [u32; 2] {
+///     [1, 2]
+/// }
+///
+/// pair(core::ptr::null_mut());
+/// ```
+struct ReturnViaPtrUnsafe;
+
+/// `nullable` makes the export `unsafe`, the caller vouches for non-null pointers
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn get(#[reprfn(nullable)] x: Option<&u32>) -> u32 {
+///     x.copied().unwrap_or(0)
+/// }
+///
+/// assert_eq!(unsafe { get(&3) }, 3);
+/// assert_eq!(unsafe { get(core::ptr::null()) }, 0);
+/// ```
+///
+/// ```compile_fail,E0133
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn get(#[reprfn(nullable)] x: Option<&u32>) -> u32 {
+///     x.copied().unwrap_or(0)
+/// }
+///
+/// get(core::ptr::null());
+/// ```
+struct NullableUnsafe;
+
+/// `slice_abi` makes the export `unsafe`, an empty slice accepts a null pointer
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn sum(#[reprfn(slice_abi)] xs: &[u32]) -> u32 {
+///     xs.iter().sum()
+/// }
+///
+/// assert_eq!(unsafe { sum([1, 2].as_ptr(), 2) }, 3);
+/// assert_eq!(unsafe { sum(core::ptr::null(), 0) }, 0);
+/// ```
+///
+/// ```compile_fail,E0133
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn sum(#[reprfn(slice_abi)] xs: &[u32]) -> u32 {
+///     xs.iter().sum()
+/// }
+///
+/// sum(core::ptr::null(), 0);
+/// ```
+struct SliceAbiUnsafe;
+
+/// `repr_int` makes the export `unsafe`, the caller vouches for a valid discriminant
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[derive(Clone, Copy, PartialEq, Debug)]
+/// pub enum Level {
+///     Low,
+///     High,
+/// }
+///
+/// impl TryFrom<u32> for Level {
+///     type Error = ();
+///
+///     fn try_from(value: u32) -> Result<Self, ()> {
+///         match value {
+///             0 => Ok(Level::Low),
+///             1 => Ok(Level::High),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// #[reprfn(abi = "C")]
+/// pub fn is_high(#[reprfn(repr_int = "u32")] level: Level) -> bool {
+///     level == Level::High
+/// }
+///
+/// assert!(unsafe { is_high(1) });
+/// assert!(!unsafe { is_high(0) });
+/// ```
+///
+/// ```compile_fail,E0133
+/// # use reprfn::reprfn;
+/// #[derive(Clone, Copy, PartialEq, Debug)]
+/// pub enum Level {
+///     Low,
+///     High,
+/// }
+///
+/// impl TryFrom<u32> for Level {
+///     type Error = ();
+///
+///     fn try_from(value: u32) -> Result<Self, ()> {
+///         match value {
+///             0 => Ok(Level::Low),
+///             1 => Ok(Level::High),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// #[reprfn(abi = "C")]
+/// pub fn is_high(#[reprfn(repr_int = "u32")] level: Level) -> bool {
+///     level == Level::High
+/// }
+///
+/// is_high(7);
+/// ```
+struct ReprIntUnsafe;
+
+/// `result_abi` with an `Ok` value makes the export `unsafe`, the caller vouches for a non-null `out`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// struct Negative;
+///
+/// impl reprfn_core::ErrorCode for Negative {
+///     fn error_code(&self) -> i32 {
+///         1
+///     }
+/// }
+///
+/// #[reprfn(abi = "C", result_abi = true)]
+/// pub fn double(x: i32) -> Result<i32, Negative> {
+///     if x < 0 { Err(Negative) } else { Ok(x * 2) }
+/// }
+///
+/// let mut out = 0;
+/// assert_eq!(unsafe { double(3, &mut out) }, 0);
+/// assert_eq!(out, 6);
+/// assert_eq!(unsafe { double(-1, core::ptr::null_mut()) }, 1);
+/// ```
+///
+/// ```compile_fail,E0133
+/// # use reprfn::reprfn;
+/// struct Negative;
+///
+/// impl reprfn_core::ErrorCode for Negative {
+///     fn error_code(&self) -> i32 {
+///         1
+///     }
+/// }
+///
+/// #[reprfn(abi = "C", result_abi = true)]
+/// pub fn double(x: i32) -> Result<i32, Negative> {
+///     if x < 0 { Err(Negative) } else { Ok(x * 2) }
+/// }
+///
+/// double(3, core::ptr::null_mut());
+/// ```
+struct ResultAbiUnsafe;
+
+/// The pointer of a swappable import is private, only the `unsafe` setter replaces it
+///
+/// ```
+/// mod ffi {
+///     use reprfn::reprfn;
+///
+///     #[reprfn(abi = "C", swappable = true, safe = true, name = "abs")]
+///     pub fn c_abs(x: i32) -> i32;
+/// }
+///
+/// extern "C" fn zero(_: i32) -> i32 {
+///     0
+/// }
+///
+/// fn main() {
+///     assert_eq!(ffi::c_abs(-3), 3);
+///     unsafe { ffi::set_c_abs(Some(zero)) };
+///     assert_eq!(ffi::c_abs(-3), 0);
+///     unsafe { ffi::set_c_abs(None) };
+///     assert_eq!(ffi::c_abs(-3), 3);
+/// }
+/// ```
+///
+/// ```compile_fail,E0603
+/// mod ffi {
+///     use reprfn::reprfn;
+///
+///     #[reprfn(abi = "C", swappable = true, safe = true, name = "abs")]
+///     pub fn c_abs(x: i32) -> i32;
+/// }
+///
+/// fn main() {
+///     ffi::C_ABS_PTR.store(core::ptr::null_mut(), core::sync::atomic::Ordering::Relaxed);
+/// }
+/// ```
+///
+/// ```compile_fail,E0133
+/// mod ffi {
+///     use reprfn::reprfn;
+///
+///     #[reprfn(abi = "C", swappable = true, safe = true, name = "abs")]
+///     pub fn c_abs(x: i32) -> i32;
+/// }
+///
+/// fn main() {
+///     ffi::set_c_abs(None);
+/// }
+/// ```
+struct SwappablePrivate;
+
+/// Options rewriting the body run it as written, with `return`, `?`, `impl Trait` and destructured parameters
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Rust", debug_trace = true)]
+/// pub fn shown((a, b): (u32, u32)) -> impl core::fmt::Display {
+///     if a == 0 {
+///         return "zero".to_string();
+///     }
+///     (a + b).to_string()
+/// }
+///
+/// pub struct Invalid;
+///
+/// impl From<core::num::TryFromIntError> for Invalid {
+///     fn from(_: core::num::TryFromIntError) -> Self {
+///         Invalid
+///     }
+/// }
+///
+/// impl reprfn_core::ErrorCode for Invalid {
+///     fn error_code(&self) -> i32 {
+///         1
+///     }
+/// }
+///
+/// #[reprfn(abi = "C", result_abi = true)]
+/// pub fn narrow(x: i64, _: u32) -> Result<(), Invalid> {
+///     u8::try_from(x)?;
+///     Ok(())
+/// }
+///
+/// assert_eq!(shown((0, 1)).to_string(), "zero");
+/// assert_eq!(shown((1, 2)).to_string(), "3");
+/// assert_eq!(narrow(3, 0), 0);
+/// assert_eq!(narrow(300, 0), 1);
+/// ```
+struct RewrittenBody;
+
+/// A private function moved into `module` stays reachable from where it was declared, also through `reexport_as`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", module = "ffi", name = "reprfn_private_add", reexport_as = add)]
+/// fn private_add(a: u32, b: u32) -> u32 {
+///     a + b
+/// }
+///
+/// mod nested {
+///     use reprfn::reprfn;
+///
+///     #[reprfn(abi = "C", module = "ffi")]
+///     pub(super) fn nested_sub(a: u32, b: u32) -> u32 {
+///         a - b
+///     }
+///
+///     #[reprfn(abi = "C", module = "local")]
+///     pub(self) fn nested_mul(a: u32, b: u32) -> u32 {
+///         a * b
+///     }
+///
+///     pub fn mul(a: u32, b: u32) -> u32 {
+///         local::nested_mul(a, b)
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(ffi::private_add(1, 2), 3);
+///     assert_eq!(add(1, 2), 3);
+///     assert_eq!(nested::ffi::nested_sub(3, 2), 1);
+///     assert_eq!(nested::mul(3, 2), 6);
+/// }
+/// ```
+struct ModulePrivate;
+
+/// `name` follows the `no_mangle` policy, both symbols are only unmangled in the builds it selects
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "reprfn_release_only", no_mangle = "release")]
+/// pub fn release_only() -> u32 {
+///     1
+/// }
+///
+/// #[cfg(not(debug_assertions))]
+/// unsafe extern "C" {
+///     fn reprfn_release_only() -> u32;
+/// }
+///
+/// assert_eq!(release_only(), 1);
+/// #[cfg(not(debug_assertions))]
+/// assert_eq!(unsafe { reprfn_release_only() }, 1);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "reprfn_never", no_mangle = "never")]
+/// pub fn never() -> u32 {
+///     1
+/// }
+/// ```
+struct NameNoMangleRelease;
+
+/// C exports keep their lifetime parameters, bounds and higher-ranked callbacks included
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn longest<'a, 'b: 'a>(a: &'a u32, b: &'b u32) -> &'a u32 {
+///     if *a >= *b { a } else { b }
+/// }
+///
+/// #[reprfn(abi = "C")]
+/// pub fn first(xs: &[u32; 2]) -> &u32 {
+///     &xs[0]
+/// }
+///
+/// #[reprfn(abi = "C")]
+/// pub fn apply<'a>(x: &'a u32, f: for<'b> extern "C" fn(&'b u32) -> &'b u32) -> &'a u32
+/// where
+///     'a: 'a,
+/// {
+///     f(x)
+/// }
+///
+/// #[reprfn(abi = "C")]
+/// pub fn apply_callback(x: &u32, #[reprfn(callback)] f: for<'b> fn(&'b u32) -> &'b u32) -> u32 {
+///     *f(x)
+/// }
+///
+/// extern "C" fn identity(x: &u32) -> &u32 {
+///     x
+/// }
+///
+/// assert_eq!(*longest(&1, &2), 2);
+/// assert_eq!(*first(&[3, 4]), 3);
+/// assert_eq!(*apply(&5, identity), 5);
+/// assert_eq!(apply_callback(&6, identity), 6);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub fn generic<'a, T>(x: &'a T) -> &'a T {
+///     x
+/// }
+/// ```
+struct ExportLifetimes;
+
+/// `uefi` entry points default to the "efiapi" ABI and keep an unmangled symbol
+///
+/// ```
+/// # use reprfn::reprfn;
+/// # use core::ffi::c_void;
+/// #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+/// #[reprfn(uefi = true, name = "reprfn_efi_main")]
+/// pub fn efi_main(_image: *mut c_void, _system_table: *mut c_void) -> usize {
+///     0
+/// }
+///
+/// #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+/// {
+///     let _: extern "efiapi" fn(*mut c_void, *mut c_void) -> usize = efi_main;
+///     assert_eq!(efi_main(core::ptr::null_mut(), core::ptr::null_mut()), 0);
+/// }
+/// ```
+///
+/// An entry point takes an image handle and a system table
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(uefi = true)]
+/// pub fn efi_main(_image: *mut core::ffi::c_void) -> usize {
+///     0
+/// }
+/// ```
+///
+/// and must use the "efiapi" ABI
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// # use core::ffi::c_void;
+/// #[reprfn(abi = "C", uefi = true)]
+/// pub fn efi_main(_image: *mut c_void, _system_table: *mut c_void) -> usize {
+///     0
+/// }
+/// ```
+struct UefiEntryPoint;
+
+/// `ptx-kernel` kernels return `()`, their results go through pointer parameters
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "ptx-kernel")]
+/// pub fn kernel(_out: *mut u32) -> u32 {
+///     1
+/// }
+/// ```
+///
+/// A valid kernel is accepted by the macro, but the ABI only exists on nvptx targets
+///
+/// ```compile_fail,E0570
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "ptx-kernel")]
+/// pub fn kernel(out: *mut u32) {
+///     unsafe { *out = 1 };
+/// }
+/// ```
+struct PtxKernel;
+
+/// `C-cmse-nonsecure-entry` exports take at most four parameters
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C-cmse-nonsecure-entry")]
+/// pub fn entry(a: u32, b: u32, c: u32, d: u32, e: u32) -> u32 {
+///     a + b + c + d + e
+/// }
+/// ```
+///
+/// and cannot be imported
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C-cmse-nonsecure-entry", mode = "import")]
+/// pub fn entry(a: u32) -> u32;
+/// ```
+///
+/// `C-cmse-nonsecure-call` is rejected on functions, it only applies to function pointers
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C-cmse-nonsecure-call")]
+/// pub fn call(a: u32) -> u32 {
+///     a
+/// }
+/// ```
+///
+/// A valid entry is accepted by the macro, but rustc rejects the ABI outside of Armv8-M targets
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C-cmse-nonsecure-entry")]
+/// pub fn entry(a: u32) -> u32 {
+///     a
+/// }
+/// ```
+struct Cmse;
+
+/// `default_version` defines the function as `<symbol>_v` on ELF targets, and the versioned `<symbol>` from it
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", default_version = "DOC_1.0")]
+/// pub fn versioned() -> u32 {
+///     1
+/// }
+///
+/// extern "C" {
+///     #[link_name = "versioned"]
+///     fn linked_versioned() -> u32;
+///     #[cfg(target_os = "linux")]
+///     fn versioned_v() -> u32;
+/// }
+///
+/// fn main() {
+///     assert_eq!(unsafe { linked_versioned() }, 1);
+///     #[cfg(target_os = "linux")]
+///     assert_eq!(unsafe { versioned_v() }, 1);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", default_version = "DOC_1.0", no_mangle = "release")]
+/// pub fn versioned() -> u32 {
+///     1
+/// }
+/// ```
+struct DefaultVersion;
+
+/// Naked functions take a `naked_asm!` body, `asm!` assumes a prologue they do not have
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[cfg(target_arch = "x86_64")]
+/// #[reprfn(abi = "C", naked = true)]
+/// pub fn answer() -> u32 {
+///     core::arch::naked_asm!("mov eax, 42", "ret")
+/// }
+///
+/// #[cfg(target_arch = "x86_64")]
+/// assert_eq!(answer(), 42);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", naked = true)]
+/// pub fn answer() -> u32 {
+///     core::arch::asm!("mov eax, 42", "ret")
+/// }
+/// ```
+///
+/// Parameter hints and options rewriting the body would add to it:
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", naked = true)]
+/// pub fn answer(#[reprfn(as = "u8")] flag: bool) -> u32 {
+///     core::arch::naked_asm!("mov eax, 42", "ret")
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", naked = true, debug_trace = true)]
+/// pub fn answer() -> u32 {
+///     core::arch::naked_asm!("mov eax, 42", "ret")
+/// }
+/// ```
+struct NakedAsm;
+
+/// `import_name` and `prefix` are checked like `name`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", mode = "forward", import_name = "abs", prefix = "mylib_")]
+/// pub fn my_abs(x: i32) -> i32 {}
+///
+/// assert_eq!(my_abs(-3), 3);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "")]
+/// pub fn empty() -> u32 {
+///     1
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", mode = "forward", import_name = "abs\0")]
+/// pub fn nul(x: i32) -> i32 {}
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", prefix = "my lib_")]
+/// pub fn spaced() -> u32 {
+///     1
+/// }
+/// ```
+struct SymbolNames;
+
+/// The return type of a `status_enum` function is the enum, or left out
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", status_enum = { Ok = 0, Busy = 1 })]
+/// pub fn poll() -> Status {
+///     Status::Busy
+/// }
+///
+/// #[reprfn(abi = "C", status_enum = Reply { Done = 0, Failed = 1 })]
+/// pub fn reply() {
+///     return Reply::Done;
+/// }
+///
+/// assert_eq!(poll(), Status::Busy);
+/// assert_eq!(reply(), Reply::Done);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", status_enum = { Ok = 0, Busy = 1 })]
+/// pub fn poll() -> u32 {
+///     1
+/// }
+/// ```
+struct StatusEnumReturn;
+
+/// Imports need an ABI, `abi = "Rust"` has to be spelled out
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Rust", name = "reprfn_doc_answer")]
+/// pub fn answer() -> u32 {
+///     42
+/// }
+///
+/// #[reprfn(abi = "Rust", mode = "import", name = "reprfn_doc_answer")]
+/// pub fn imported_answer() -> u32;
+///
+/// assert_eq!(unsafe { imported_answer() }, 42);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(mode = "import")]
+/// pub fn answer() -> u32;
+/// ```
+///
+/// Statics too:
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "reprfn_doc_limit")]
+/// pub static LIMIT: u32 = 7;
+///
+/// #[reprfn(abi = "C", mode = "import", name = "reprfn_doc_limit")]
+/// pub static IMPORTED_LIMIT: u32;
+///
+/// assert_eq!(unsafe { IMPORTED_LIMIT }, 7);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(mode = "import", name = "reprfn_doc_limit")]
+/// pub static IMPORTED_LIMIT: u32;
+/// ```
+struct ImportAbi;
+
+/// Values outside of those an option accepts are errors
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Cobol")]
+/// pub fn f() {
+///     let _x = 1;
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", mode = "sideways")]
+/// pub fn f() {
+///     let _x = 1;
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", inline = "sometimes")]
+/// pub fn f() {
+///     let _x = 1;
+/// }
+/// ```
+struct InvalidValues;
+
+/// Options that contradict each other, or the mode of the function, are errors
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", cold = true, inline = "always")]
+/// pub fn f() {
+///     let _x = 1;
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", ffi_pure = true, ffi_const = true)]
+/// pub fn f() -> u32;
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", inline = "always")]
+/// pub fn f();
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", no_sanitize = "address")]
+/// pub fn f();
+/// ```
+struct ConflictingOptions;
+
+/// A static is exported under its name, and `accessor` exports a function returning its address
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "doc_counter", accessor = "doc_counter_addr")]
+/// pub static mut COUNTER: u32 = 5;
+///
+/// extern "C" {
+///     #[link_name = "doc_counter"]
+///     static LINKED_COUNTER: u32;
+///     #[link_name = "doc_counter_addr"]
+///     fn linked_counter_addr() -> *mut u32;
+/// }
+///
+/// unsafe { *linked_counter_addr() += 1 };
+/// assert_eq!(unsafe { LINKED_COUNTER }, 6);
+/// assert_eq!(doc_counter_addr(), unsafe { core::ptr::addr_of_mut!(COUNTER) });
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "doc_counter", accessor = "doc_counter_addr")]
+/// pub static COUNTER: u32;
+/// ```
+struct StaticAccessor;
+
+/// Rust ABI exports are plain functions keeping their mangled symbol, `keep_extern` spells out `extern "Rust"`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Rust")]
+/// pub fn plain() -> u32 {
+///     1
+/// }
+///
+/// #[reprfn(abi = "Rust", keep_extern = true)]
+/// pub fn kept() -> u32 {
+///     2
+/// }
+///
+/// let functions: [fn() -> u32; 2] = [plain, kept];
+/// assert_eq!(functions.map(|f| f()), [1, 2]);
+/// ```
+struct RustAbiExport;
+
+/// `validate_only` emits the function unchanged, a Rust function still, but reports errors as usual
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "doc_validated", validate_only = true)]
+/// pub fn validated() -> u32 {
+///     1
+/// }
+///
+/// let f: fn() -> u32 = validated;
+/// assert_eq!(f(), 1);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Cobol", validate_only = true)]
+/// pub fn validated() -> u32 {
+///     1
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", validate_onyl = true)]
+/// pub fn validated() -> u32 {
+///     1
+/// }
+/// ```
+struct ValidateOnly;
+
+/// `tuple_return` returns the tuple as a `#[repr(C)]` struct with fields `_0`, `_1`, ...
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", tuple_return = "DivMod")]
+/// pub fn div_mod(a: u32, b: u32) -> (u32, u32) {
+///     if b == 0 {
+///         return (0, 0);
+///     }
+///     (a / b, a % b)
+/// }
+///
+/// extern "C" {
+///     #[link_name = "div_mod"]
+///     fn c_div_mod(a: u32, b: u32) -> DivMod;
+/// }
+///
+/// let DivMod { _0: quotient, _1: remainder } = unsafe { c_div_mod(7, 2) };
+/// assert_eq!((quotient, remainder), (3, 1));
+/// assert_eq!(div_mod(1, 0)._0, 0);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", tuple_return = "DivMod")]
+/// pub fn div_mod(a: u32, b: u32) -> u32 {
+///     a / b
+/// }
+/// ```
+struct TupleReturn;
+
+/// `multiversion` builds the body once per feature, each build returning the same result
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", multiversion = ["avx2", "sse2", "baseline"])]
+/// pub fn dot(a: u32, b: u32) -> u32 {
+///     a * b + 1
+/// }
+///
+/// extern "C" {
+///     #[link_name = "dot"]
+///     fn c_dot(a: u32, b: u32) -> u32;
+/// }
+///
+/// assert_eq!(dot(2, 3), 7);
+/// assert_eq!(unsafe { c_dot(4, 5) }, 21);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", multiversion = ["avx2"])]
+/// pub fn dot(a: u32, b: u32) -> u32 {
+///     a * b + 1
+/// }
+/// ```
+struct Multiversion;
+
+/// `ctor` runs the export when the binary is loaded, before `main`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// use core::sync::atomic::{AtomicBool, Ordering};
+///
+/// static LOADED: AtomicBool = AtomicBool::new(false);
+///
+/// #[reprfn(abi = "C", ctor = true)]
+/// pub fn doc_on_load() {
+///     LOADED.store(true, Ordering::Relaxed);
+/// }
+///
+/// #[reprfn(abi = "C", dtor = true)]
+/// pub fn doc_on_unload() {
+///     LOADED.store(false, Ordering::Relaxed);
+/// }
+///
+/// fn main() {
+///     assert!(LOADED.load(Ordering::Relaxed));
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", ctor = true)]
+/// pub fn doc_on_load(x: u32) {
+///     let _ = x;
+/// }
+/// ```
+struct CtorDtor;
+
+/// `since` notes the version at the end of the documentation, the function itself is exported as usual
+///
+/// ```
+/// # use reprfn::reprfn;
+/// /// Returns the answer
+/// #[reprfn(abi = "C", since = "1.2.0")]
+/// pub fn doc_since() -> u32 {
+///     42
+/// }
+///
+/// assert_eq!(doc_since(), 42);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", since = 1.2)]
+/// pub fn doc_since() -> u32 {
+///     42
+/// }
+/// ```
+struct Since;
+
+/// `string_return` hands a `String` to C as a pointer, freed by the generated `<name>_free`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", string_return = "cstring")]
+/// pub fn greeting(nul: bool) -> String {
+///     if nul {
+///         return "a\0b".to_string();
+///     }
+///     "hello".to_string()
+/// }
+///
+/// extern "C" {
+///     #[link_name = "greeting"]
+///     fn c_greeting(nul: bool) -> *mut core::ffi::c_char;
+///     #[link_name = "greeting_free"]
+///     fn c_greeting_free(p: *mut core::ffi::c_char);
+/// }
+///
+/// let p = unsafe { c_greeting(false) };
+/// assert_eq!(unsafe { core::ffi::CStr::from_ptr(p) }.to_str(), Ok("hello"));
+/// unsafe { c_greeting_free(p) };
+/// assert!(unsafe { c_greeting(true) }.is_null());
+/// unsafe { greeting_free(core::ptr::null_mut()) };
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", string_return = "cstring")]
+/// pub fn greeting() -> &'static str {
+///     "hello"
+/// }
+/// ```
+struct StringReturn;
+
+/// `bare` exports stay plain Rust functions
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(bare = true)]
+/// pub fn double(x: u32) -> u32 {
+///     x * 2
+/// }
+///
+/// let f: fn(u32) -> u32 = double;
+/// assert_eq!(f(21), 42);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(bare = true, name = "my_double")]
+/// pub fn double(x: u32) -> u32 {
+///     x * 2
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", bare = true)]
+/// pub fn double(x: u32) -> u32 {
+///     x * 2
+/// }
+/// ```
+struct Bare;
+
+/// `link` takes the kind and modifiers of the library
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", link = { name = "m", kind = "dylib" })]
+/// pub fn cos(x: f64) -> f64;
+///
+/// assert_eq!(unsafe { cos(0.0) }, 1.0);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", link = { name = "m", kind = "shared" })]
+/// pub fn cos(x: f64) -> f64;
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", link = { name = "m", modifiers = "whole-archive" })]
+/// pub fn cos(x: f64) -> f64;
+/// ```
+struct LinkKind;
+
+/// `ordinal` needs a `raw-dylib` import, emitting `#[link_ordinal(N)]` is checked by the unit tests of
+/// `reprfn-core` as `raw-dylib` only links on Windows
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", link = "kernel32", ordinal = 3)]
+/// pub fn beep(freq: u32, duration: u32) -> i32;
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", link = { name = "kernel32", kind = "raw-dylib" }, ordinal = 3)]
+/// pub fn beep(freq: u32, duration: u32) -> i32 {
+///     0
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", link = { name = "kernel32", kind = "dylib", import_name_type = "undecorated" })]
+/// pub fn beep(freq: u32, duration: u32) -> i32;
+/// ```
+struct Ordinal;
+
+/// `check_only` keeps the ABI without exporting a symbol
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", check_only = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// let f: extern "C" fn(i32, i32) -> i32 = add;
+/// assert_eq!(f(2, 3), 5);
+/// ```
+///
+/// ```compile_fail
+/// #![deny(improper_ctypes_definitions)]
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", check_only = true)]
+/// pub fn len(s: String) -> usize {
+///     s.len()
+/// }
+/// # fn main() {}
+/// ```
+struct CheckOnly;
+
+/// `errno` returns the value and stores the code of errors aside
+///
+/// ```
+/// # use reprfn::reprfn;
+/// use std::cell::Cell;
+///
+/// struct Negative;
+///
+/// impl reprfn_core::ErrorCode for Negative {
+///     fn error_code(&self) -> i32 {
+///         22
+///     }
+/// }
+///
+/// #[reprfn(abi = "C", errno = true)]
+/// pub fn double(x: i32) -> Result<i32, Negative> {
+///     if x < 0 { Err(Negative) } else { Ok(x * 2) }
+/// }
+///
+/// #[reprfn(abi = "C", errno = true)]
+/// pub fn count(x: i32) -> Result<u32, Negative> {
+///     if x < 0 { Err(Negative) } else { Ok(x as u32) }
+/// }
+///
+/// thread_local! {
+///     static CODE: Cell<i32> = const { Cell::new(0) };
+/// }
+///
+/// fn set_code(code: i32) {
+///     CODE.with(|cell| cell.set(code));
+/// }
+///
+/// #[reprfn(abi = "C", errno = "set_code")]
+/// pub fn halve(x: i32) -> Result<i32, Negative> {
+///     if x < 0 { Err(Negative) } else { Ok(x / 2) }
+/// }
+///
+/// assert_eq!(double(3), 6);
+/// assert_eq!(double(-1), -1);
+/// assert_eq!(reprfn_core::last_error(), 22);
+/// assert_eq!(count(-1), u32::MAX);
+/// assert_eq!(halve(-1), -1);
+/// assert_eq!(CODE.with(Cell::get), 22);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", errno = true)]
+/// pub fn double(x: i32) -> i32 {
+///     x * 2
+/// }
+/// ```
+struct Errno;
+
+/// `abi` per target exports one function for the current target
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = { windows = "system", default = "C" }, name = "target_add")]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// extern "C" {
+///     #[link_name = "target_add"]
+///     fn c_add(a: i32, b: i32) -> i32;
+/// }
+///
+/// #[cfg(not(windows))]
+/// let f: extern "C" fn(i32, i32) -> i32 = add;
+/// #[cfg(windows)]
+/// let f: extern "system" fn(i32, i32) -> i32 = add;
+/// assert_eq!(f(2, 3), 5);
+/// assert_eq!(unsafe { c_add(2, 3) }, 5);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = { windows = "system", default = "C" })]
+/// pub fn add(a: i32, b: i32) -> i32;
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = { windows = "system", default = "C" })]
+/// pub fn first<T: Copy>(items: &[T]) -> T {
+///     items[0]
+/// }
+/// ```
+struct AbiPerTarget;
+
+/// Default methods of a `trait` are exported as free functions, the trait keeps them
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub trait Plugin {
+///     fn plugin_version() -> u32 {
+///         3
+///     }
+///
+///     fn name(&self) -> &str;
+/// }
+///
+/// struct Demo;
+///
+/// impl Plugin for Demo {
+///     fn name(&self) -> &str {
+///         "demo"
+///     }
+/// }
+///
+/// extern "C" {
+///     #[link_name = "plugin_version"]
+///     fn c_plugin_version() -> u32;
+/// }
+///
+/// assert_eq!(plugin_version(), 3);
+/// assert_eq!(unsafe { c_plugin_version() }, 3);
+/// assert_eq!(<Demo as Plugin>::plugin_version(), 3);
+/// assert_eq!(Demo.name(), "demo");
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub trait Plugin {
+///     fn id(&self) -> u32 {
+///         1
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C")]
+/// pub trait Plugin {
+///     fn size() -> usize {
+///         core::mem::size_of::<Self>()
+///     }
+/// }
+/// ```
+struct TraitDefaults;
+
+/// `underscore_alias` defines the symbol a second time with a leading underscore
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "alias_add", underscore_alias = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// extern "C" {
+///     fn alias_add(a: i32, b: i32) -> i32;
+///     fn _alias_add(a: i32, b: i32) -> i32;
+/// }
+///
+/// fn main() {
+///     assert_eq!(unsafe { alias_add(2, 3) }, 5);
+///     assert_eq!(unsafe { _alias_add(2, 3) }, 5);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Rust", underscore_alias = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "add.v1", underscore_alias = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+/// ```
+struct UnderscoreAlias;
+
+/// `deep_abi` gives nested function pointer types the ABI of the function
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", deep_abi = true)]
+/// pub fn apply(f: fn(fn(i32) -> i32, i32) -> i32, g: Option<fn(i32) -> i32>, x: i32) -> fn(i32) -> i32 {
+///     f(g.unwrap(), x);
+///     g.unwrap()
+/// }
+///
+/// extern "C" fn double(x: i32) -> i32 {
+///     x * 2
+/// }
+///
+/// extern "C" fn call(f: extern "C" fn(i32) -> i32, x: i32) -> i32 {
+///     f(x)
+/// }
+///
+/// let g: extern "C" fn(i32) -> i32 = apply(call, Some(double), 1);
+/// assert_eq!(g(21), 42);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(deep_abi = true)]
+/// pub fn apply(f: fn(i32) -> i32, x: i32) -> i32 {
+///     f(x)
+/// }
+/// ```
+struct DeepAbi;
+
+/// `anon` defines the symbol without a Rust item
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "anon_add", anon = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// extern "C" {
+///     fn anon_add(a: i32, b: i32) -> i32;
+/// }
+///
+/// assert_eq!(unsafe { anon_add(2, 3) }, 5);
+/// ```
+///
+/// ```compile_fail,E0425
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "anon_add", anon = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// add(2, 3);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "Rust", anon = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+/// ```
+struct Anon;
+
+/// `from_name_convention` links imports against the converted name
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "Sdl_CreateWindow")]
+/// pub fn create(width: u32) -> u32 {
+///     width * 2
+/// }
+///
+/// #[reprfn(abi = "C", name = "getWindowWidth")]
+/// pub fn width(window: u32) -> u32 {
+///     window / 2
+/// }
+///
+/// mod sdl {
+///     use reprfn::reprfn;
+///
+///     #[reprfn(abi = "C", from_name_convention = "LibCamel")]
+///     pub fn sdl_create_window(width: u32) -> u32;
+///
+///     #[reprfn(abi = "C", from_name_convention = "camelCase")]
+///     pub fn get_window_width(window: u32) -> u32;
+/// }
+///
+/// let window = unsafe { sdl::sdl_create_window(640) };
+/// assert_eq!(unsafe { sdl::get_window_width(window) }, 640);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", from_name_convention = "camelCase")]
+/// pub fn sha_256(data: *const u8, len: usize) -> u32;
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", from_name_convention = "camelCase")]
+/// pub fn get_width(window: u32) -> u32 {
+///     window
+/// }
+/// ```
+struct FromNameConvention;
+
+/// `assert_abi_layout` checks the sizes of the exported signature
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", assert_abi_layout = { ctx = 8, len = 4, return = 4 })]
+/// pub fn count(ctx: u64, len: u32) -> u32 {
+///     len + ctx as u32
+/// }
+///
+/// assert_eq!(count(1, 2), 3);
+/// ```
+///
+/// ```compile_fail,E0308
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", assert_abi_layout = { ctx = 4 })]
+/// pub fn count(ctx: u64, len: u32) -> u32 {
+///     len + ctx as u32
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", assert_abi_layout = { size = 4 })]
+/// pub fn count(ctx: u64, len: u32) -> u32 {
+///     len + ctx as u32
+/// }
+/// ```
+struct AssertAbiLayout;
+
+/// `mode` per feature emits neither the export nor the import without its feature, the cfgs of both modes are
+/// checked by the unit tests of `reprfn-core`
+///
+/// ```compile_fail,E0425
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", mode = { export = "build-lib", import = "build-app" })]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// add(2, 3);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", mode = { export = "build-lib", import = "build-app" })]
+/// pub fn add(a: i32, b: i32) -> i32;
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", mode = { export = "build-lib" })]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+/// ```
+struct ModeFeatures;
+
+/// `hidden` leaves exports and imports out of the docs, so `missing_docs` does not ask for them
+///
+/// ```
+/// #![deny(missing_docs)]
+/// //! Hidden items
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "hidden_add", hidden = true)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// #[reprfn(abi = "C", link_name = "hidden_add", hidden = true)]
+/// pub fn c_add(a: i32, b: i32) -> i32;
+///
+/// fn main() {
+///     assert_eq!(add(2, 3), 5);
+///     assert_eq!(unsafe { c_add(2, 3) }, 5);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #![deny(missing_docs)]
+/// //! Hidden items
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "shown_add")]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+/// # fn main() {}
+/// ```
+struct Hidden;
+
+/// `wasm_bindgen` keeps the C export with its hints on other targets, the `wasm32` export is checked by the unit
+/// tests of `reprfn-core`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "wasm_is_even", wasm_bindgen = true)]
+/// pub fn is_even(#[reprfn(as = "u8")] strict: bool, x: u32) -> bool {
+///     !strict || x % 2 == 0
+/// }
+///
+/// extern "C" {
+///     fn wasm_is_even(strict: u8, x: u32) -> bool;
+/// }
+///
+/// assert!(unsafe { wasm_is_even(1, 4) });
+/// assert!(!unsafe { wasm_is_even(1, 3) });
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", wasm_bindgen = true)]
+/// pub fn first<T: Copy>(items: &[T]) -> T {
+///     items[0]
+/// }
+/// ```
+struct WasmBindgen;
+
+/// `doc` documents the function, after the docs written on it
+///
+/// ```
+/// #![deny(missing_docs)]
+/// //! Documented items
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "doc_add", doc = "Adds two numbers", doc = "Never overflows in practice")]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// fn main() {
+///     assert_eq!(add(2, 3), 5);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", doc = 1)]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+/// ```
+struct Doc;
+
+/// `applies_to = "export"` keeps the wrapper of a swappable import without the feature, `applies_to = "wrapper"`
+/// gates only the wrapper
+///
+/// ```
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", swappable = true, safe = true, name = "reprfn_not_linked", feature = { on = "ffi", applies_to = "export" })]
+/// pub fn compute(x: i32) -> i32;
+///
+/// extern "C" fn double(x: i32) -> i32 {
+///     x * 2
+/// }
+///
+/// assert!(std::panic::catch_unwind(|| compute(1)).is_err());
+/// unsafe { set_compute(Some(double)) };
+/// assert_eq!(compute(21), 42);
+/// ```
+///
+/// ```compile_fail,E0425
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", import_style = "item", name = "abs", feature = { on = "ffi", applies_to = "wrapper" })]
+/// pub fn c_abs(x: i32) -> i32;
+///
+/// unsafe { c_abs(-3) };
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", name = "abs", feature = { on = "ffi", applies_to = "wrapper" })]
+/// pub fn c_abs(x: i32) -> i32;
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", import_style = "item", name = "abs", feature = { on = "ffi", applies_to = "export" })]
+/// pub fn c_abs(x: i32) -> i32;
+/// ```
+struct FeatureAppliesTo;
+
+/// `import_cfg` keeps exports as they are without `--cfg reprfn_import`, the import the cfg selects is checked by
+/// the unit tests of `reprfn-core`
+///
+/// ```
+/// #![allow(unexpected_cfgs)]
+/// #[reprfn::reprfn_defaults(abi = "C", import_cfg = true)]
+/// use reprfn::reprfn;
+///
+/// #[reprfn(name = "cfg_add")]
+/// pub fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// extern "C" {
+///     fn cfg_add(a: i32, b: i32) -> i32;
+/// }
+///
+/// fn main() {
+///     assert_eq!(add(2, 3), 5);
+///     assert_eq!(unsafe { cfg_add(2, 3) }, 5);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[reprfn::reprfn_defaults(import_cfg = "yes")]
+/// use reprfn::reprfn;
+/// ```
+struct ImportCfg;
+
+/// `opaque` returns a box as a handle, dropped by the generated `<name>_destroy`
+///
+/// ```
+/// # use reprfn::reprfn;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+///
+/// static DROPPED: AtomicU32 = AtomicU32::new(0);
+///
+/// pub struct Session {
+///     id: u32,
+/// }
+///
+/// impl Drop for Session {
+///     fn drop(&mut self) {
+///         DROPPED.fetch_add(self.id, Ordering::Relaxed);
+///     }
+/// }
+///
+/// #[reprfn(abi = "C", name = "session_open", opaque = true)]
+/// pub fn open(id: u32) -> Box<Session> {
+///     Box::new(Session { id })
+/// }
+///
+/// extern "C" {
+///     fn session_open(id: u32) -> *mut Session;
+///     fn session_open_destroy(p: *mut Session);
+/// }
+///
+/// let handle = unsafe { session_open(7) };
+/// assert_eq!(unsafe { (*handle).id }, 7);
+/// unsafe { session_open_destroy(handle) };
+/// assert_eq!(DROPPED.load(Ordering::Relaxed), 7);
+/// unsafe { open_destroy(core::ptr::null_mut()) };
+/// assert_eq!(DROPPED.load(Ordering::Relaxed), 7);
+/// ```
+///
+/// ```compile_fail
+/// # use reprfn::reprfn;
+/// #[reprfn(abi = "C", opaque = true)]
+/// pub fn open(id: u32) -> u32 {
+///     id
+/// }
+/// ```
+struct Opaque;